@@ -1,249 +1,1912 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token_interface::{TokenInterface, Mint, TokenAccount};
+use anchor_lang::solana_program::program_error::ProgramError;
+use anchor_lang::system_program;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+use spl_tlv_account_resolution::{account::ExtraAccountMeta, seeds::Seed, state::ExtraAccountMetaList};
+use spl_token_2022::extension::{
+    transfer_hook::TransferHookAccount, BaseStateWithExtensions, StateWithExtensions,
+};
+use spl_transfer_hook_interface::instruction::{ExecuteInstruction, TransferHookInstruction};
 
 declare_id!("9JJWgpjTmmXYNhsUgqanojpfGdL5ovQTPaF53Gb8qX4J");
 
+// discriminator + user + status + level + created_at + expires_at + revoked_at (Option)
+// + revocation_count + reinstatement_count + reinstated_at (Option) + reinstatement_reason
+// + country + accredited
+const KYC_DATA_SPACE: usize = 8 + 32 + 1 + 1 + 8 + 8 + 1 + 8 + 4 + 4 + 1 + 8 + 2 + 2 + 1;
+
+// Most countries a JurisdictionPolicy can list
+const MAX_POLICY_COUNTRIES: usize = 64;
+
+// How long a UserUsage account must go unused before its owner can close it
+const USAGE_IDLE_DAYS: i64 = 30;
+
+// Rolling usage windows: 24 hourly buckets back the daily limit, 30 daily
+// buckets back the optional weekly (last 7) and monthly (all 30) caps.
+const HOURLY_BUCKETS: usize = 24;
+const DAILY_BUCKETS: usize = 30;
+const DAYS_PER_WEEK: usize = 7;
+
+// discriminator + user + mint + hourly_used + hourly_transfers + current_hour + daily_used
+// + current_day + last_transaction
+const USER_USAGE_SPACE: usize =
+    8 + 32 + 32 + 8 * HOURLY_BUCKETS + 4 * HOURLY_BUCKETS + 8 + 8 * DAILY_BUCKETS + 8 + 8;
+
+// daily_limit + transaction_limit + weekly_limit + monthly_limit + max_daily_transfers
+// + cooldown_seconds + is_active + updated_at
+const MINT_LIMITS_SPACE: usize = 8 + 8 + 9 + 9 + 5 + 5 + 1 + 8;
+
+// discriminator + mint + authority + require_sender_kyc + require_recipient_kyc
+// + limits_enabled + max_balance + allowlist_only + is_paused + updated_at
+// + one limits row per KYC tier
+const MINT_CONFIG_SPACE: usize =
+    8 + 32 + 32 + 1 + 1 + 1 + 9 + 1 + 1 + 8 + MINT_LIMITS_SPACE * KycLevel::ALL.len();
+
 #[program]
 pub mod hook {
     use super::*;
 
-    pub fn validate_transfer(ctx: Context<ValidateTransfer>) -> Result<()> {
-        let kyc_account = &ctx.accounts.kyc;
-        if kyc_account.status != 1 {
-            return Err(error!(ErrorCode::KycFailed));
+    pub fn validate_transfer(ctx: Context<ValidateTransfer>, amount: u64) -> Result<()> {
+        check_is_transferring(&ctx.accounts.source)?;
+        require!(ctx.accounts.settings.is_active, ErrorCode::HookPaused);
+        let now = Clock::get()?.unix_timestamp;
+
+        // Denylisted wallets can neither send nor receive, exempt or not
+        require!(
+            ctx.accounts.source_denylist_entry.data_is_empty(),
+            ErrorCode::SourceDenylisted
+        );
+        require!(
+            ctx.accounts.destination_denylist_entry.data_is_empty(),
+            ErrorCode::DestinationDenylisted
+        );
+
+        // Locked tokens stay put whatever else applies. Token-2022 runs the hook
+        // after moving the funds, so the source balance is already post-transfer.
+        if let Some(lockup) = load_optional::<Lockup>(&ctx.accounts.lockup)? {
+            require!(
+                ctx.accounts.source.amount >= lockup.locked_amount(now),
+                ErrorCode::LockupViolation
+            );
+        }
+
+        // Mints without a config account get the default policy
+        let config = load_optional::<MintConfig>(&ctx.accounts.mint_config)?;
+        require!(!config.as_ref().is_some_and(|config| config.is_paused), ErrorCode::MintPaused);
+        let policy = config.as_ref().map_or_else(MintPolicy::default, MintConfig::policy);
+
+        // Closed-loop mints only move between allowlisted owners, whatever
+        // their KYC status or exemptions. Delegates are deliberately not
+        // checked: the tokens stay with listed holders either way, and an
+        // extra lookup for the signer would not fit Token-2022's meta heap.
+        if policy.allowlist_only {
+            require!(
+                !ctx.accounts.source_allowlist_entry.data_is_empty(),
+                ErrorCode::SourceNotAllowlisted
+            );
+            require!(
+                !ctx.accounts.destination_allowlist_entry.data_is_empty(),
+                ErrorCode::DestinationNotAllowlisted
+            );
+        }
+
+        // Exempt senders (pool vaults, treasuries, bridges) skip KYC and limits.
+        // Sender checks apply to the holder of the source account, not to a
+        // delegate authorizing the transfer on its behalf.
+        let source_exempt = ctx.accounts.source_exempt()?;
+        let kyc_account = load_optional::<KycData>(&ctx.accounts.source_kyc)?;
+        if !source_exempt {
+            if policy.require_sender_kyc {
+                let kyc = kyc_account.as_ref().ok_or_else(|| error!(ErrorCode::KycFailed))?;
+                if kyc.status != 1 {
+                    return Err(error!(ErrorCode::KycFailed));
+                }
+                require!(!kyc.is_expired(now), ErrorCode::KycExpired);
+            }
+
+            // Apply the limit row for the holder's KYC tier, if one has been set;
+            // senders without a KYC record are held to the Basic row
+            if let (true, Some(config)) = (policy.limits_enabled, config.as_ref()) {
+                let level = kyc_account.as_ref().map_or(KycLevel::Basic, |kyc| kyc.level);
+                let limits = config.limits_for(level);
+                if limits.is_active {
+                    require!(amount <= limits.transaction_limit, ErrorCode::TransferLimitExceeded);
+                    ctx.accounts.apply_usage_limits(limits, amount, now)?;
+                }
+            }
+        }
+
+        // Mints can additionally require the receiving wallet to be KYC'd
+        let destination_exempt = ctx.accounts.destination_exempt()?;
+        let recipient_kyc = load_optional::<KycData>(&ctx.accounts.destination_kyc)?;
+        if policy.require_recipient_kyc && !destination_exempt {
+            require!(
+                recipient_kyc.as_ref().is_some_and(|kyc| kyc.is_valid(now)),
+                ErrorCode::RecipientKycFailed
+            );
+        }
+
+        // Cap what any one token account may hold; Token-2022 has already
+        // credited the destination, so its balance here includes this transfer
+        if let (Some(max_balance), false) = (policy.max_balance, destination_exempt) {
+            require!(
+                ctx.accounts.destination.amount <= max_balance,
+                ErrorCode::MaxBalanceExceeded
+            );
+        }
+
+        // ...and restrict which jurisdictions may send or receive it
+        let jurisdiction = load_optional::<JurisdictionPolicy>(&ctx.accounts.jurisdiction_policy)?;
+        if let Some(jurisdiction) = jurisdiction {
+            require!(
+                source_exempt || jurisdiction.permits(kyc_account.as_ref()),
+                ErrorCode::SourceJurisdictionBlocked
+            );
+            require!(
+                destination_exempt || jurisdiction.permits(recipient_kyc.as_ref()),
+                ErrorCode::DestinationJurisdictionBlocked
+            );
         }
         msg!("Transfer validated successfully for user: {}", ctx.accounts.owner.key());
         Ok(())
     }
 
-    pub fn create_kyc(ctx: Context<CreateKyc>) -> Result<()> {
+    pub fn initialize_extra_account_meta_list(
+        ctx: Context<InitializeExtraAccountMetaList>,
+    ) -> Result<()> {
+        let account_metas = extra_account_metas()?;
+        let mut data = ctx.accounts.extra_account_meta_list.try_borrow_mut_data()?;
+        ExtraAccountMetaList::init::<ExecuteInstruction>(&mut data, &account_metas)?;
+        msg!("Extra account metas initialized for mint: {}", ctx.accounts.mint.key());
+        Ok(())
+    }
+
+    // Rewrites the meta list of an existing mint after the hook starts requiring
+    // new accounts. The account only holds this one TLV entry, so it is cleared
+    // and laid out again at its new size.
+    pub fn update_extra_account_meta_list(
+        ctx: Context<UpdateExtraAccountMetaList>,
+    ) -> Result<()> {
+        let account_metas = extra_account_metas()?;
+        let meta_list = &ctx.accounts.extra_account_meta_list;
+        let new_len = ExtraAccountMetaList::size_of(account_metas.len())?;
+
+        let rent_due = Rent::get()?
+            .minimum_balance(new_len)
+            .saturating_sub(meta_list.lamports());
+        if rent_due > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.authority.to_account_info(),
+                        to: meta_list.to_account_info(),
+                    },
+                ),
+                rent_due,
+            )?;
+        }
+        meta_list.realloc(new_len, false)?;
+
+        let mut data = meta_list.try_borrow_mut_data()?;
+        data.fill(0);
+        ExtraAccountMetaList::init::<ExecuteInstruction>(&mut data, &account_metas)?;
+        msg!("Extra account metas updated for mint: {}", ctx.accounts.mint.key());
+        Ok(())
+    }
+
+    // Token-2022 calls the hook with the SPL transfer hook `Execute` instruction
+    // instead of an Anchor discriminator, so route it to validate_transfer here.
+    pub fn fallback<'info>(
+        program_id: &Pubkey,
+        accounts: &'info [AccountInfo<'info>],
+        data: &[u8],
+    ) -> Result<()> {
+        match TransferHookInstruction::unpack(data)? {
+            TransferHookInstruction::Execute { amount } => {
+                let amount_bytes = amount.to_le_bytes();
+                __private::__global::validate_transfer(program_id, accounts, &amount_bytes)
+            }
+            _ => Err(ProgramError::InvalidInstructionData.into()),
+        }
+    }
+
+    pub fn create_kyc(ctx: Context<CreateKyc>, level: KycLevel, expires_at: i64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(expires_at > now, ErrorCode::InvalidExpiry);
+
         let kyc = &mut ctx.accounts.kyc;
         kyc.user = ctx.accounts.user.key();
         kyc.status = 1; // Approved
-        kyc.created_at = Clock::get()?.unix_timestamp;
+        kyc.level = level;
+        kyc.created_at = now;
+        kyc.expires_at = expires_at;
         kyc.revoked_at = None;
-        msg!("KYC created for user: {}", kyc.user);
+        msg!("KYC created for user: {} ({:?}, expires {})", kyc.user, level, expires_at);
+        Ok(())
+    }
+
+    pub fn renew_kyc(ctx: Context<RenewKyc>, level: KycLevel, expires_at: i64) -> Result<()> {
+        require!(expires_at > Clock::get()?.unix_timestamp, ErrorCode::InvalidExpiry);
+
+        let kyc = &mut ctx.accounts.kyc;
+        // Revoked approvals cannot be brought back by a renewal
+        require!(kyc.status == 1, ErrorCode::KycRevoked);
+        kyc.level = level;
+        kyc.expires_at = expires_at;
+        msg!("KYC renewed for user: {} ({:?}, expires {})", kyc.user, level, expires_at);
+        Ok(())
+    }
+
+    pub fn set_kyc_jurisdiction(
+        ctx: Context<SetKycJurisdiction>,
+        country: [u8; 2],
+        accredited: bool,
+    ) -> Result<()> {
+        require!(is_country_code(&country), ErrorCode::InvalidCountryCode);
+        let kyc = &mut ctx.accounts.kyc;
+        kyc.country = country;
+        kyc.accredited = accredited;
+        msg!(
+            "KYC jurisdiction for user {}: {} (accredited: {})",
+            kyc.user,
+            String::from_utf8_lossy(&country),
+            accredited
+        );
         Ok(())
     }
 
     pub fn revoke_kyc(ctx: Context<RevokeKyc>) -> Result<()> {
         let kyc = &mut ctx.accounts.kyc;
-        kyc.status = 0; // Revoked
-        kyc.revoked_at = Some(Clock::get()?.unix_timestamp);
+        require!(kyc.status == 1, ErrorCode::KycRevoked);
+        kyc.revoke(Clock::get()?.unix_timestamp);
         msg!("KYC revoked for user: {}", kyc.user);
         Ok(())
     }
 
-    pub fn update_authority(ctx: Context<UpdateAuthority>) -> Result<()> {
+    // Brings a revoked record back once the user has cleared review. The
+    // reason code is opaque to the program and kept for audit.
+    pub fn reinstate_kyc(ctx: Context<ReinstateKyc>, reason: u16, expires_at: i64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(reason != 0, ErrorCode::MissingReasonCode);
+        require!(expires_at > now, ErrorCode::InvalidExpiry);
+
+        let kyc = &mut ctx.accounts.kyc;
+        require!(kyc.status == 0, ErrorCode::KycNotRevoked);
+        kyc.status = 1; // Approved
+        kyc.expires_at = expires_at;
+        kyc.reinstatement_count = kyc.reinstatement_count.saturating_add(1);
+        kyc.reinstated_at = Some(now);
+        kyc.reinstatement_reason = reason;
+        msg!("KYC reinstated for user: {} (reason {})", kyc.user, reason);
+        Ok(())
+    }
+
+    // Leaves a tombstone with the record's history behind, so the user cannot
+    // be given a clean record by create_kyc afterwards.
+    pub fn close_kyc(ctx: Context<CloseKyc>) -> Result<()> {
+        let kyc = &ctx.accounts.kyc;
+        let tombstone = &mut ctx.accounts.kyc_tombstone;
+        tombstone.user = kyc.user;
+        tombstone.revocation_count = kyc.revocation_count;
+        tombstone.reinstatement_count = kyc.reinstatement_count;
+        tombstone.closed_at = Clock::get()?.unix_timestamp;
+        msg!(
+            "KYC closed for user: {}, rent returned to {}",
+            ctx.accounts.kyc.user,
+            ctx.accounts.recipient.key()
+        );
+        Ok(())
+    }
+
+    // Approves every user in `remaining_accounts`, passed as (user, kyc PDA,
+    // tombstone PDA) triples, at the same level and expiry. Users that already
+    // have a KYC record are skipped so a partially applied batch can simply be
+    // resent, as are users whose record was closed.
+    pub fn create_kyc_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CreateKycBatch<'info>>,
+        level: KycLevel,
+        expires_at: i64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(expires_at > now, ErrorCode::InvalidExpiry);
+
+        let triples = ctx.remaining_accounts.chunks_exact(3);
+        require!(triples.remainder().is_empty(), ErrorCode::InvalidBatch);
+
+        let mut created = 0u32;
+        for triple in triples {
+            let (user, kyc_info, tombstone) = (&triple[0], &triple[1], &triple[2]);
+            let (expected, bump) =
+                Pubkey::find_program_address(&[b"kyc", user.key.as_ref()], &crate::ID);
+            require_keys_eq!(kyc_info.key(), expected, ErrorCode::InvalidKycAccount);
+            let (expected, _) =
+                Pubkey::find_program_address(&[b"kyc-tombstone", user.key.as_ref()], &crate::ID);
+            require_keys_eq!(tombstone.key(), expected, ErrorCode::InvalidKycAccount);
+
+            if kyc_info.owner == &crate::ID {
+                msg!("KYC already exists for user: {}", user.key());
+                continue;
+            }
+            if !tombstone.data_is_empty() {
+                msg!("KYC record was closed for user: {}", user.key());
+                continue;
+            }
+
+            create_pda_account(
+                &ctx.accounts.authority,
+                kyc_info,
+                &ctx.accounts.system_program,
+                KYC_DATA_SPACE,
+                &[b"kyc", user.key.as_ref(), &[bump]],
+            )?;
+            let kyc = KycData {
+                user: user.key(),
+                status: 1, // Approved
+                level,
+                created_at: now,
+                expires_at,
+                revoked_at: None,
+                revocation_count: 0,
+                reinstatement_count: 0,
+                reinstated_at: None,
+                reinstatement_reason: 0,
+                country: [0; 2],
+                accredited: false,
+            };
+            kyc.try_serialize(&mut &mut kyc_info.try_borrow_mut_data()?[..])?;
+            created += 1;
+        }
+        msg!("KYC created for {} users ({:?}, expires {})", created, level, expires_at);
+        Ok(())
+    }
+
+    // Revokes every KYC account passed in `remaining_accounts`.
+    pub fn revoke_kyc_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RevokeKycBatch<'info>>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let mut revoked = 0u32;
+        for kyc_info in ctx.remaining_accounts {
+            require!(kyc_info.is_writable, ErrorCode::InvalidKycAccount);
+            let mut kyc = Account::<KycData>::try_from(kyc_info)?;
+            let (expected, _) =
+                Pubkey::find_program_address(&[b"kyc", kyc.user.as_ref()], &crate::ID);
+            require_keys_eq!(kyc_info.key(), expected, ErrorCode::InvalidKycAccount);
+
+            // Already revoked records are left alone so their history stays accurate
+            if kyc.status == 0 {
+                continue;
+            }
+            kyc.revoke(now);
+            kyc.exit(&crate::ID)?;
+            revoked += 1;
+        }
+        msg!("KYC revoked for {} users", revoked);
+        Ok(())
+    }
+
+    pub fn add_exempt_account(ctx: Context<AddExemptAccount>, address: Pubkey) -> Result<()> {
+        let exempt = &mut ctx.accounts.exempt_account;
+        exempt.address = address;
+        exempt.added_at = Clock::get()?.unix_timestamp;
+        msg!("Exempt account added: {}", address);
+        Ok(())
+    }
+
+    pub fn remove_exempt_account(ctx: Context<RemoveExemptAccount>) -> Result<()> {
+        msg!("Exempt account removed: {}", ctx.accounts.exempt_account.address);
+        Ok(())
+    }
+
+    pub fn add_to_denylist(ctx: Context<AddToDenylist>, wallet: Pubkey, reason: u16) -> Result<()> {
+        let entry = &mut ctx.accounts.denylist_entry;
+        entry.wallet = wallet;
+        entry.reason = reason;
+        entry.added_at = Clock::get()?.unix_timestamp;
+        msg!("Wallet denylisted: {} (reason {})", wallet, reason);
+        Ok(())
+    }
+
+    pub fn remove_from_denylist(ctx: Context<RemoveFromDenylist>) -> Result<()> {
+        msg!("Wallet removed from denylist: {}", ctx.accounts.denylist_entry.wallet);
+        Ok(())
+    }
+
+    pub fn grant_role(ctx: Context<GrantRole>, member: Pubkey, role: Role) -> Result<()> {
+        // Admins manage the other roles; only the authority can appoint admins
+        if role == Role::Admin {
+            require_keys_eq!(
+                ctx.accounts.authority.key(),
+                ctx.accounts.settings.authority,
+                ErrorCode::Unauthorized
+            );
+        }
+        let assignment = &mut ctx.accounts.role_assignment;
+        assignment.member = member;
+        assignment.role = role;
+        assignment.granted_at = Clock::get()?.unix_timestamp;
+        msg!("Role {:?} granted to: {}", role, member);
+        Ok(())
+    }
+
+    pub fn revoke_role(ctx: Context<RevokeRole>) -> Result<()> {
+        let assignment = &ctx.accounts.role_assignment;
+        if assignment.role == Role::Admin {
+            require_keys_eq!(
+                ctx.accounts.authority.key(),
+                ctx.accounts.settings.authority,
+                ErrorCode::Unauthorized
+            );
+        }
+        msg!("Role {:?} revoked from: {}", assignment.role, assignment.member);
+        Ok(())
+    }
+
+    // Authority handover is two-step so a mistyped key cannot take control:
+    // the current authority proposes and the new key must accept.
+    pub fn propose_authority(ctx: Context<ProposeAuthority>, new_authority: Pubkey) -> Result<()> {
+        let settings = &mut ctx.accounts.settings;
+        settings.pending_authority = Some(new_authority);
+        settings.updated_at = Clock::get()?.unix_timestamp;
+        msg!("Authority transfer proposed to: {}", new_authority);
+        Ok(())
+    }
+
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
         let settings = &mut ctx.accounts.settings;
         settings.authority = ctx.accounts.new_authority.key();
+        settings.pending_authority = None;
         settings.updated_at = Clock::get()?.unix_timestamp;
         msg!("Authority updated to: {}", settings.authority);
         Ok(())
     }
 
+    pub fn cancel_authority_transfer(ctx: Context<CancelAuthorityTransfer>) -> Result<()> {
+        let settings = &mut ctx.accounts.settings;
+        require!(settings.pending_authority.is_some(), ErrorCode::NoPendingAuthority);
+        settings.pending_authority = None;
+        settings.updated_at = Clock::get()?.unix_timestamp;
+        msg!("Authority transfer cancelled");
+        Ok(())
+    }
+
     pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
         let settings = &mut ctx.accounts.settings;
         settings.authority = ctx.accounts.authority.key();
         settings.created_at = Clock::get()?.unix_timestamp;
         settings.is_active = true;
         settings.updated_at = Clock::get()?.unix_timestamp;
+        settings.pending_authority = None;
         msg!("Hook program initialized with authority: {}", settings.authority);
         Ok(())
     }
 
-    pub fn set_transfer_limits(ctx: Context<SetTransferLimits>, limits: TransferLimits) -> Result<()> {
-        let mint_limits = &mut ctx.accounts.mint_limits;
-        mint_limits.mint = ctx.accounts.mint.key();
-        mint_limits.daily_limit = limits.daily_limit;
-        mint_limits.transaction_limit = limits.transaction_limit;
+    /// Creates the mint's config. `policy.max_balance` caps each token account,
+    /// not each owner: a holder may spread tokens over several accounts.
+    pub fn initialize_mint_config(
+        ctx: Context<InitializeMintConfig>,
+        authority: Pubkey,
+        policy: MintPolicy,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.mint_config;
+        config.mint = ctx.accounts.mint.key();
+        config.authority = authority;
+        config.apply(&policy);
+        config.is_paused = false;
+        config.updated_at = Clock::get()?.unix_timestamp;
+        msg!("Mint config initialized for mint: {}", config.mint);
+        Ok(())
+    }
+
+    /// Replaces the mint's policy; `max_balance` is a per-token-account cap.
+    pub fn set_mint_policy(ctx: Context<UpdateMintConfig>, policy: MintPolicy) -> Result<()> {
+        let config = &mut ctx.accounts.mint_config;
+        config.apply(&policy);
+        config.updated_at = Clock::get()?.unix_timestamp;
+        msg!("Policy updated for mint: {}", config.mint);
+        Ok(())
+    }
+
+    pub fn set_mint_paused(ctx: Context<UpdateMintConfig>, paused: bool) -> Result<()> {
+        let config = &mut ctx.accounts.mint_config;
+        config.is_paused = paused;
+        config.updated_at = Clock::get()?.unix_timestamp;
+        msg!("Mint {} paused: {}", config.mint, paused);
+        Ok(())
+    }
+
+    pub fn set_mint_config_authority(
+        ctx: Context<UpdateMintConfig>,
+        new_authority: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.mint_config;
+        config.authority = new_authority;
+        config.updated_at = Clock::get()?.unix_timestamp;
+        msg!("Mint config authority for {} set to: {}", config.mint, new_authority);
+        Ok(())
+    }
+
+    pub fn create_lockup(ctx: Context<CreateLockup>, schedule: LockupSchedule) -> Result<()> {
+        require!(
+            schedule.amount > 0
+                && schedule.start_at < schedule.end_at
+                && (schedule.start_at..=schedule.end_at).contains(&schedule.cliff_at),
+            ErrorCode::InvalidLockupSchedule
+        );
+        let lockup = &mut ctx.accounts.lockup;
+        lockup.token_account = ctx.accounts.token_account.key();
+        lockup.mint = ctx.accounts.mint.key();
+        lockup.amount = schedule.amount;
+        lockup.start_at = schedule.start_at;
+        lockup.cliff_at = schedule.cliff_at;
+        lockup.end_at = schedule.end_at;
+        lockup.created_at = Clock::get()?.unix_timestamp;
+        msg!("Lockup of {} created for account: {}", lockup.amount, lockup.token_account);
+        Ok(())
+    }
+
+    // Admins and the mint's config authority may lift a lockup at any time;
+    // the token account's current owner may close it once everything has vested.
+    pub fn release_lockup(ctx: Context<ReleaseLockup>) -> Result<()> {
+        let lockup = &ctx.accounts.lockup;
+        let signer = ctx.accounts.authority.key();
+        let is_manager = has_role(&ctx.accounts.settings, &signer, &ctx.accounts.role_assignment, Role::Admin)
+            || ctx.accounts.mint_config.as_ref().is_some_and(|config| config.authority == signer);
+        if !is_manager {
+            let owner = ctx.accounts.token_account.as_ref().map(|account| account.owner);
+            require!(owner == Some(signer), ErrorCode::Unauthorized);
+            require!(
+                lockup.locked_amount(Clock::get()?.unix_timestamp) == 0,
+                ErrorCode::LockupStillActive
+            );
+        }
+        msg!("Lockup released for account: {}", lockup.token_account);
+        Ok(())
+    }
+
+    pub fn initialize_jurisdiction_policy(
+        ctx: Context<InitializeJurisdictionPolicy>,
+        rules: JurisdictionRules,
+    ) -> Result<()> {
+        let policy = &mut ctx.accounts.jurisdiction_policy;
+        policy.mint = ctx.accounts.mint.key();
+        policy.apply(rules)?;
+        policy.updated_at = Clock::get()?.unix_timestamp;
+        msg!("Jurisdiction policy initialized for mint: {}", policy.mint);
+        Ok(())
+    }
+
+    pub fn update_jurisdiction_policy(
+        ctx: Context<UpdateJurisdictionPolicy>,
+        rules: JurisdictionRules,
+    ) -> Result<()> {
+        let policy = &mut ctx.accounts.jurisdiction_policy;
+        policy.apply(rules)?;
+        policy.updated_at = Clock::get()?.unix_timestamp;
+        msg!("Jurisdiction policy updated for mint: {}", policy.mint);
+        Ok(())
+    }
+
+    pub fn add_to_allowlist(ctx: Context<AddToAllowlist>, wallet: Pubkey) -> Result<()> {
+        let entry = &mut ctx.accounts.allowlist_entry;
+        entry.mint = ctx.accounts.mint.key();
+        entry.wallet = wallet;
+        entry.added_at = Clock::get()?.unix_timestamp;
+        msg!("Wallet allowlisted for mint {}: {}", entry.mint, wallet);
+        Ok(())
+    }
+
+    pub fn remove_from_allowlist(ctx: Context<RemoveFromAllowlist>) -> Result<()> {
+        let entry = &ctx.accounts.allowlist_entry;
+        msg!("Wallet removed from allowlist for mint {}: {}", entry.mint, entry.wallet);
+        Ok(())
+    }
+
+    pub fn close_user_usage(ctx: Context<CloseUserUsage>) -> Result<()> {
+        let user_usage = &ctx.accounts.user_usage;
+        let now = Clock::get()?.unix_timestamp;
+        // Both the last transfer and the last accounted day must be old enough,
+        // so closing cannot wipe usage still inside a rolling window.
+        require!(
+            now.saturating_sub(user_usage.last_transaction) >= USAGE_IDLE_DAYS * 86400
+                && now / 86400 - user_usage.current_day >= USAGE_IDLE_DAYS,
+            ErrorCode::UsageStillActive
+        );
+        msg!("Usage account closed for user: {}", ctx.accounts.user.key());
+        Ok(())
+    }
+
+    pub fn set_hook_active(ctx: Context<SetHookActive>, active: bool) -> Result<()> {
+        let settings = &mut ctx.accounts.settings;
+        settings.is_active = active;
+        settings.updated_at = Clock::get()?.unix_timestamp;
+        msg!("Hook active: {}", active);
+        Ok(())
+    }
+
+    pub fn set_transfer_limits(
+        ctx: Context<SetTransferLimits>,
+        level: KycLevel,
+        limits: TransferLimits,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.mint_config;
+        let mint_limits = config.limits_for_mut(level);
+        // Rows are set once; later changes go through update_transfer_limits
+        require!(mint_limits.updated_at == 0, ErrorCode::TransferLimitsAlreadySet);
+        mint_limits.apply(&limits);
         mint_limits.is_active = true;
         mint_limits.updated_at = Clock::get()?.unix_timestamp;
-        msg!("Transfer limits set for mint: {}", mint_limits.mint);
+        msg!("Transfer limits set for mint: {} ({:?})", config.mint, level);
         Ok(())
     }
 
-    pub fn check_transfer_limits(ctx: Context<CheckTransferLimits>, amount: u64) -> Result<()> {
-        let mint_limits = &ctx.accounts.mint_limits;
+    pub fn update_transfer_limits(
+        ctx: Context<UpdateTransferLimits>,
+        level: KycLevel,
+        limits: TransferLimits,
+        is_active: bool,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.mint_config;
+        let mint_limits = config.limits_for_mut(level);
+        let previous = mint_limits.limits();
+        let was_active = mint_limits.is_active;
+
+        mint_limits.apply(&limits);
+        mint_limits.is_active = is_active;
+        mint_limits.updated_at = Clock::get()?.unix_timestamp;
+
+        let updated_at = mint_limits.updated_at;
+        emit!(TransferLimitsUpdated {
+            mint: config.mint,
+            level,
+            previous,
+            was_active,
+            current: limits,
+            is_active,
+            updated_by: ctx.accounts.authority.key(),
+            updated_at,
+        });
+        msg!("Transfer limits updated for mint: {} ({:?})", config.mint, level);
+        Ok(())
+    }
+
+    pub fn init_user_usage(ctx: Context<InitUserUsage>) -> Result<()> {
         let user_usage = &mut ctx.accounts.user_usage;
+        user_usage.user = ctx.accounts.user.key();
+        user_usage.mint = ctx.accounts.mint.key();
+        user_usage.roll(Clock::get()?.unix_timestamp);
+        user_usage.last_transaction = 0;
+        msg!("Usage tracking initialized for user: {}", user_usage.user);
+        Ok(())
+    }
+
+    // Dry run of the limits validate_transfer applies for the caller's own KYC
+    // tier: fails as a transfer of `amount` would right now, but records nothing.
+    pub fn check_transfer_limits(ctx: Context<CheckTransferLimits>, amount: u64) -> Result<()> {
+        let mint_limits = ctx.accounts.mint_config.limits_for(ctx.accounts.kyc.level);
+        let now = Clock::get()?.unix_timestamp;
         
         // Check transaction limit
         require!(amount <= mint_limits.transaction_limit, ErrorCode::TransferLimitExceeded);
         
-        // Check daily limit
-        let current_day = Clock::get()?.unix_timestamp / 86400; // Days since epoch
-        
-        if user_usage.last_reset_day != current_day {
-            user_usage.daily_used = 0;
-            user_usage.last_reset_day = current_day;
-        }
-        
-        require!(
-            user_usage.daily_used + amount <= mint_limits.daily_limit,
-            ErrorCode::DailyLimitExceeded
-        );
-        
-        user_usage.daily_used += amount;
-        user_usage.last_transaction = Clock::get()?.unix_timestamp;
-        user_usage.user = ctx.accounts.user.key();
+        let mut user_usage = UserUsage::clone(&ctx.accounts.user_usage);
+        user_usage.roll(now);
+        user_usage.check(mint_limits, amount, now)?;
         
         msg!("Transfer limits checked successfully for amount: {}", amount);
         Ok(())
     }
 }
 
+// Extra accounts Token-2022 resolves and appends to every `Execute` call, in the
+// order validate_transfer expects them.
+fn extra_account_metas() -> Result<Vec<ExtraAccountMeta>> {
+    let mut metas = vec![
+        // kyc PDA of the source token account's owner (bytes 32..64 of account
+        // index 0), so delegates cannot stand in for the holder
+        ExtraAccountMeta::new_with_seeds(
+            &[
+                Seed::Literal { bytes: b"kyc".to_vec() },
+                Seed::AccountData { account_index: 0, data_index: 32, length: 32 },
+            ],
+            false,
+            false,
+        )?,
+    ];
+    // Per-mint config (account index 1), including the limits row for each KYC tier
+    metas.push(ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal { bytes: b"mint-config".to_vec() },
+            Seed::AccountKey { index: 1 },
+        ],
+        false,
+        false,
+    )?);
+    // kyc PDA of the destination token account's owner (bytes 32..64 of account index 2)
+    metas.push(ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal { bytes: b"kyc".to_vec() },
+            Seed::AccountData { account_index: 2, data_index: 32, length: 32 },
+        ],
+        false,
+        false,
+    )?);
+    // Exemption entries for the source account, source owner, destination
+    // account and destination owner
+    for address_seed in [
+        Seed::AccountKey { index: 0 },
+        Seed::AccountData { account_index: 0, data_index: 32, length: 32 },
+        Seed::AccountKey { index: 2 },
+        Seed::AccountData { account_index: 2, data_index: 32, length: 32 },
+    ] {
+        metas.push(ExtraAccountMeta::new_with_seeds(
+            &[Seed::Literal { bytes: b"exempt".to_vec() }, address_seed],
+            false,
+            false,
+        )?);
+    }
+    // Denylist entries for the source and destination token account owners
+    for account_index in [0, 2] {
+        metas.push(ExtraAccountMeta::new_with_seeds(
+            &[
+                Seed::Literal { bytes: b"deny".to_vec() },
+                Seed::AccountData { account_index, data_index: 32, length: 32 },
+            ],
+            false,
+            false,
+        )?);
+    }
+    // Global settings, for the pause switch
+    metas.push(ExtraAccountMeta::new_with_seeds(
+        &[Seed::Literal { bytes: b"settings".to_vec() }],
+        false,
+        false,
+    )?);
+    // Usage account of the source token account's owner for this mint, written
+    // by the tier limits
+    metas.push(ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal { bytes: b"usage".to_vec() },
+            Seed::AccountData { account_index: 0, data_index: 32, length: 32 },
+            Seed::AccountKey { index: 1 },
+        ],
+        false,
+        true,
+    )?);
+    // Lockup of the source token account itself (account index 0)
+    metas.push(ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal { bytes: b"lockup".to_vec() },
+            Seed::AccountKey { index: 0 },
+        ],
+        false,
+        false,
+    )?);
+    // Jurisdiction policy of the mint (account index 1)
+    metas.push(ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal { bytes: b"jurisdiction".to_vec() },
+            Seed::AccountKey { index: 1 },
+        ],
+        false,
+        false,
+    )?);
+    // Allowlist entries of the source and destination token account owners for
+    // this mint, checked in allowlist-only mode
+    for account_index in [0, 2] {
+        metas.push(ExtraAccountMeta::new_with_seeds(
+            &[
+                Seed::Literal { bytes: b"allow".to_vec() },
+                Seed::AccountKey { index: 1 },
+                Seed::AccountData { account_index, data_index: 32, length: 32 },
+            ],
+            false,
+            false,
+        )?);
+    }
+    Ok(metas)
+}
+
+// PDAs passed as extra accounts may not have been created yet; an empty account
+// means the setting is not configured.
+fn load_optional<T: AccountDeserialize + Owner>(info: &AccountInfo) -> Result<Option<T>> {
+    if info.data_is_empty() {
+        return Ok(None);
+    }
+    require_keys_eq!(*info.owner, T::owner(), ErrorCode::InvalidAccountOwner);
+    let data = info.try_borrow_data()?;
+    T::try_deserialize(&mut &data[..]).map(Some)
+}
+
+// Creates a program-owned PDA the way Anchor's `init` does, also covering an
+// address that was pre-funded to block account creation.
+fn create_pda_account<'info>(
+    payer: &Signer<'info>,
+    account: &AccountInfo<'info>,
+    system: &Program<'info, System>,
+    space: usize,
+    seeds: &[&[u8]],
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+    let signer_seeds = &[seeds];
+
+    if account.lamports() == 0 {
+        return system_program::create_account(
+            CpiContext::new_with_signer(
+                system.to_account_info(),
+                system_program::CreateAccount {
+                    from: payer.to_account_info(),
+                    to: account.clone(),
+                },
+                signer_seeds,
+            ),
+            rent,
+            space as u64,
+            &crate::ID,
+        );
+    }
+
+    let shortfall = rent.saturating_sub(account.lamports());
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system.to_account_info(),
+                system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: account.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system.to_account_info(),
+            system_program::Allocate {
+                account_to_allocate: account.clone(),
+            },
+            signer_seeds,
+        ),
+        space as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system.to_account_info(),
+            system_program::Assign {
+                account_to_assign: account.clone(),
+            },
+            signer_seeds,
+        ),
+        &crate::ID,
+    )
+}
+
+// Whether `signer` may act as `role`: the settings authority holds every role,
+// anyone else needs an assignment. Assignments are only ever created at their
+// `[b"role", member, role]` PDA, so matching the fields stands in for the seeds.
+fn has_role(
+    settings: &ProgramSettings,
+    signer: &Pubkey,
+    assignment: &Option<Account<RoleAssignment>>,
+    role: Role,
+) -> bool {
+    settings.authority == *signer
+        || assignment
+            .as_ref()
+            .is_some_and(|assignment| assignment.member == *signer && assignment.role == role)
+}
+
+// Rejects direct calls to the hook outside of a Token-2022 transfer.
+fn check_is_transferring(source: &InterfaceAccount<TokenAccount>) -> Result<()> {
+    let source_info = source.to_account_info();
+    let data = source_info.try_borrow_data()?;
+    let account = StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)?;
+    let extension = account.get_extension::<TransferHookAccount>()?;
+    require!(bool::from(extension.transferring), ErrorCode::NotTransferring);
+    Ok(())
+}
+
+// Account order follows the SPL transfer hook `Execute` instruction: source, mint,
+// destination, owner and the extra account meta list, then the extra accounts.
+// Token-2022 derives each extra account from the meta list before invoking the
+// hook and check_is_transferring rules out direct calls, so their seeds are not
+// derived again here.
+#[derive(Accounts)]
+pub struct ValidateTransfer<'info> {
+    /// Source token account
+    pub source: InterfaceAccount<'info, TokenAccount>,
+
+    /// The mint being transferred
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Destination token account
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+
+    /// The owner (or delegate) authorizing the transfer
+    /// CHECK: Token-2022 has already verified the transfer authority
+    pub owner: AccountInfo<'info>,
+
+    /// CHECK: Extra account meta list for this mint, read by Token-2022
+    #[account(
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: AccountInfo<'info>,
+
+    /// CHECK: KYC account of the source account owner, may not exist for exempt senders
+    pub source_kyc: AccountInfo<'info>,
+
+    /// CHECK: Per-mint config, may not exist
+    pub mint_config: AccountInfo<'info>,
+
+    /// CHECK: KYC account of the destination owner, may not exist
+    pub destination_kyc: AccountInfo<'info>,
+
+    /// CHECK: Exemption entry for the source token account, may not exist
+    pub source_exemption: AccountInfo<'info>,
+
+    /// CHECK: Exemption entry for the source account owner, may not exist
+    pub source_owner_exemption: AccountInfo<'info>,
+
+    /// CHECK: Exemption entry for the destination token account, may not exist
+    pub destination_exemption: AccountInfo<'info>,
+
+    /// CHECK: Exemption entry for the destination owner, may not exist
+    pub destination_owner_exemption: AccountInfo<'info>,
+
+    /// CHECK: Denylist entry for the source account owner, exists only if denied
+    pub source_denylist_entry: AccountInfo<'info>,
+
+    /// CHECK: Denylist entry for the destination account owner, exists only if denied
+    pub destination_denylist_entry: AccountInfo<'info>,
+
+    pub settings: Account<'info, ProgramSettings>,
+
+    /// CHECK: Usage account of the source account owner for this mint, may not exist
+    #[account(mut)]
+    pub user_usage: AccountInfo<'info>,
+
+    /// CHECK: Lockup of the source token account, may not exist
+    pub lockup: AccountInfo<'info>,
+
+    /// CHECK: Jurisdiction policy of the mint, may not exist
+    pub jurisdiction_policy: AccountInfo<'info>,
+
+    /// CHECK: Allowlist entry for the source account owner, exists only if listed
+    pub source_allowlist_entry: AccountInfo<'info>,
+
+    /// CHECK: Allowlist entry for the destination account owner, exists only if listed
+    pub destination_allowlist_entry: AccountInfo<'info>,
+}
+
+impl<'info> ValidateTransfer<'info> {
+    // Enforces the tier's rolling caps, cooldown and transfer count on the
+    // holder's usage account, then counts this transfer against them.
+    fn apply_usage_limits(&self, limits: &MintLimits, amount: u64, now: i64) -> Result<()> {
+        let mut usage = load_optional::<UserUsage>(&self.user_usage)?
+            .ok_or_else(|| error!(ErrorCode::UsageNotInitialized))?;
+        usage.roll(now);
+        usage.check(limits, amount, now)?;
+        usage.record(amount)?;
+        usage.record_transfer(now);
+
+        let mut data = self.user_usage.try_borrow_mut_data()?;
+        usage.try_serialize(&mut &mut data[..])
+    }
+
+    fn source_exempt(&self) -> Result<bool> {
+        Ok(load_optional::<ExemptAccount>(&self.source_exemption)?.is_some()
+            || load_optional::<ExemptAccount>(&self.source_owner_exemption)?.is_some())
+    }
+
+    fn destination_exempt(&self) -> Result<bool> {
+        Ok(load_optional::<ExemptAccount>(&self.destination_exemption)?.is_some()
+            || load_optional::<ExemptAccount>(&self.destination_owner_exemption)?.is_some())
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializeExtraAccountMetaList<'info> {
+    /// CHECK: Extra account meta list, laid out by spl-tlv-account-resolution
+    #[account(
+        init,
+        payer = authority,
+        space = ExtraAccountMetaList::size_of(extra_account_metas()?.len())?,
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: AccountInfo<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"settings"],
+        bump
+    )]
+    pub settings: Account<'info, ProgramSettings>,
+
+    /// Role held by a signer other than the settings authority
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+
+    #[account(
+        mut,
+        constraint = has_role(&settings, &authority.key(), &role_assignment, Role::Admin) @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateExtraAccountMetaList<'info> {
+    /// CHECK: Extra account meta list, laid out by spl-tlv-account-resolution
+    #[account(
+        mut,
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: AccountInfo<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"settings"],
+        bump
+    )]
+    pub settings: Account<'info, ProgramSettings>,
+
+    /// Role held by a signer other than the settings authority
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+
+    #[account(
+        mut,
+        constraint = has_role(&settings, &authority.key(), &role_assignment, Role::Admin) @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateKyc<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = KYC_DATA_SPACE,
+        seeds = [b"kyc", user.key().as_ref()],
+        bump
+    )]
+    pub kyc: Account<'info, KycData>,
+
+    /// User who will be KYC approved
+    /// CHECK: Can be any account
+    pub user: AccountInfo<'info>,
+
+    /// CHECK: Tombstone of a closed record for the user, must not exist
+    #[account(
+        seeds = [b"kyc-tombstone", user.key().as_ref()],
+        bump,
+        constraint = kyc_tombstone.data_is_empty() @ ErrorCode::KycRecordClosed
+    )]
+    pub kyc_tombstone: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"settings"],
+        bump
+    )]
+    pub settings: Account<'info, ProgramSettings>,
+
+    /// Role held by a signer other than the settings authority
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+
+    #[account(
+        mut,
+        constraint = has_role(&settings, &authority.key(), &role_assignment, Role::KycOperator) @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeKyc<'info> {
+    #[account(
+        mut,
+        seeds = [b"kyc", user.key().as_ref()],
+        bump,
+        constraint = kyc.user == user.key() @ ErrorCode::InvalidKycAccount
+    )]
+    pub kyc: Account<'info, KycData>,
+
+    /// User whose KYC will be revoked
+    /// CHECK: Can be any account
+    pub user: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"settings"],
+        bump
+    )]
+    pub settings: Account<'info, ProgramSettings>,
+
+    /// Role held by a signer other than the settings authority
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+
+    #[account(
+        constraint = has_role(&settings, &authority.key(), &role_assignment, Role::KycOperator) @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseKyc<'info> {
+    #[account(
+        mut,
+        close = recipient,
+        seeds = [b"kyc", kyc.user.as_ref()],
+        bump,
+        constraint = kyc.status == 0 @ ErrorCode::KycNotRevoked
+    )]
+    pub kyc: Account<'info, KycData>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 4 + 4 + 8, // user + revocation_count + reinstatement_count + closed_at
+        seeds = [b"kyc-tombstone", kyc.user.as_ref()],
+        bump
+    )]
+    pub kyc_tombstone: Account<'info, KycTombstone>,
+
+    /// CHECK: Receives the reclaimed rent
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"settings"],
+        bump
+    )]
+    pub settings: Account<'info, ProgramSettings>,
+
+    /// Role held by a signer other than the settings authority
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+
+    #[account(
+        mut,
+        constraint = has_role(&settings, &authority.key(), &role_assignment, Role::KycOperator) @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateKycBatch<'info> {
+    #[account(
+        seeds = [b"settings"],
+        bump
+    )]
+    pub settings: Account<'info, ProgramSettings>,
+
+    /// Role held by a signer other than the settings authority
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+
+    #[account(
+        mut,
+        constraint = has_role(&settings, &authority.key(), &role_assignment, Role::KycOperator) @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeKycBatch<'info> {
+    #[account(
+        seeds = [b"settings"],
+        bump
+    )]
+    pub settings: Account<'info, ProgramSettings>,
+
+    /// Role held by a signer other than the settings authority
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+
+    #[account(
+        constraint = has_role(&settings, &authority.key(), &role_assignment, Role::KycOperator) @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReinstateKyc<'info> {
+    #[account(
+        mut,
+        seeds = [b"kyc", user.key().as_ref()],
+        bump,
+        constraint = kyc.user == user.key() @ ErrorCode::InvalidKycAccount
+    )]
+    pub kyc: Account<'info, KycData>,
+
+    /// User whose KYC will be reinstated
+    /// CHECK: Can be any account
+    pub user: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"settings"],
+        bump
+    )]
+    pub settings: Account<'info, ProgramSettings>,
+
+    /// Role held by a signer other than the settings authority
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+
+    #[account(
+        constraint = has_role(&settings, &authority.key(), &role_assignment, Role::KycOperator) @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RenewKyc<'info> {
+    #[account(
+        mut,
+        seeds = [b"kyc", user.key().as_ref()],
+        bump,
+        constraint = kyc.user == user.key() @ ErrorCode::InvalidKycAccount
+    )]
+    pub kyc: Account<'info, KycData>,
+
+    /// User whose KYC will be renewed
+    /// CHECK: Can be any account
+    pub user: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"settings"],
+        bump
+    )]
+    pub settings: Account<'info, ProgramSettings>,
+
+    /// Role held by a signer other than the settings authority
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+
+    #[account(
+        constraint = has_role(&settings, &authority.key(), &role_assignment, Role::KycOperator) @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetKycJurisdiction<'info> {
+    #[account(
+        mut,
+        seeds = [b"kyc", user.key().as_ref()],
+        bump,
+        constraint = kyc.user == user.key() @ ErrorCode::InvalidKycAccount
+    )]
+    pub kyc: Account<'info, KycData>,
+
+    /// CHECK: Can be any account
+    pub user: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"settings"],
+        bump
+    )]
+    pub settings: Account<'info, ProgramSettings>,
+
+    /// Role held by a signer other than the settings authority
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+
+    #[account(
+        constraint = has_role(&settings, &authority.key(), &role_assignment, Role::KycOperator) @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(address: Pubkey)]
+pub struct AddExemptAccount<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 8, // address + added_at
+        seeds = [b"exempt", address.as_ref()],
+        bump
+    )]
+    pub exempt_account: Account<'info, ExemptAccount>,
+
+    #[account(
+        seeds = [b"settings"],
+        bump
+    )]
+    pub settings: Account<'info, ProgramSettings>,
+
+    /// Role held by a signer other than the settings authority
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+
+    #[account(
+        mut,
+        constraint = has_role(&settings, &authority.key(), &role_assignment, Role::Admin) @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveExemptAccount<'info> {
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"exempt", exempt_account.address.as_ref()],
+        bump
+    )]
+    pub exempt_account: Account<'info, ExemptAccount>,
+
+    #[account(
+        seeds = [b"settings"],
+        bump
+    )]
+    pub settings: Account<'info, ProgramSettings>,
+
+    /// Role held by a signer other than the settings authority
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+
+    #[account(
+        mut,
+        constraint = has_role(&settings, &authority.key(), &role_assignment, Role::Admin) @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct AddToDenylist<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 2 + 8, // wallet + reason + added_at
+        seeds = [b"deny", wallet.as_ref()],
+        bump
+    )]
+    pub denylist_entry: Account<'info, DenylistEntry>,
+
+    #[account(
+        seeds = [b"settings"],
+        bump
+    )]
+    pub settings: Account<'info, ProgramSettings>,
+
+    /// Role held by a signer other than the settings authority
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+
+    #[account(
+        mut,
+        constraint = has_role(&settings, &authority.key(), &role_assignment, Role::Admin) @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveFromDenylist<'info> {
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"deny", denylist_entry.wallet.as_ref()],
+        bump
+    )]
+    pub denylist_entry: Account<'info, DenylistEntry>,
+
+    #[account(
+        seeds = [b"settings"],
+        bump
+    )]
+    pub settings: Account<'info, ProgramSettings>,
+
+    /// Role held by a signer other than the settings authority
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+
+    #[account(
+        mut,
+        constraint = has_role(&settings, &authority.key(), &role_assignment, Role::Admin) @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(member: Pubkey, role: Role)]
+pub struct GrantRole<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 1 + 8, // member + role + granted_at
+        seeds = [b"role", member.as_ref(), &[role as u8]],
+        bump
+    )]
+    pub role_assignment: Account<'info, RoleAssignment>,
+
+    #[account(
+        seeds = [b"settings"],
+        bump
+    )]
+    pub settings: Account<'info, ProgramSettings>,
+
+    /// Admin role of a signer other than the settings authority
+    pub admin_role: Option<Account<'info, RoleAssignment>>,
+
+    #[account(
+        mut,
+        constraint = has_role(&settings, &authority.key(), &admin_role, Role::Admin) @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeRole<'info> {
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"role", role_assignment.member.as_ref(), &[role_assignment.role as u8]],
+        bump
+    )]
+    pub role_assignment: Account<'info, RoleAssignment>,
+
+    #[account(
+        seeds = [b"settings"],
+        bump
+    )]
+    pub settings: Account<'info, ProgramSettings>,
+
+    /// Admin role of a signer other than the settings authority
+    pub admin_role: Option<Account<'info, RoleAssignment>>,
+
+    #[account(
+        mut,
+        constraint = has_role(&settings, &authority.key(), &admin_role, Role::Admin) @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeAuthority<'info> {
+    #[account(
+        mut,
+        seeds = [b"settings"],
+        bump,
+        constraint = settings.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub settings: Account<'info, ProgramSettings>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    #[account(
+        mut,
+        seeds = [b"settings"],
+        bump,
+        constraint = settings.pending_authority == Some(new_authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub settings: Account<'info, ProgramSettings>,
+
+    pub new_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelAuthorityTransfer<'info> {
+    #[account(
+        mut,
+        seeds = [b"settings"],
+        bump,
+        constraint = settings.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub settings: Account<'info, ProgramSettings>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 8 + 1 + 8 + 1 + 32, // authority + created_at + is_active + updated_at + pending_authority (Option)
+        seeds = [b"settings"],
+        bump
+    )]
+    pub settings: Account<'info, ProgramSettings>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeMintConfig<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = MINT_CONFIG_SPACE,
+        seeds = [b"mint-config", mint.key().as_ref()],
+        bump
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"settings"],
+        bump
+    )]
+    pub settings: Account<'info, ProgramSettings>,
+
+    /// Role held by a signer other than the settings authority
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+
+    #[account(
+        mut,
+        constraint = has_role(&settings, &authority.key(), &role_assignment, Role::Admin) @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMintConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"mint-config", mint_config.mint.as_ref()],
+        bump
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        seeds = [b"settings"],
+        bump
+    )]
+    pub settings: Account<'info, ProgramSettings>,
+
+    /// Role held by a signer other than the settings authority
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+
+    #[account(
+        constraint = mint_config.authority == authority.key()
+            || has_role(&settings, &authority.key(), &role_assignment, Role::Admin) @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateLockup<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 8, // token_account + mint + amount + start_at + cliff_at + end_at + created_at
+        seeds = [b"lockup", token_account.key().as_ref()],
+        bump
+    )]
+    pub lockup: Account<'info, Lockup>,
+
+    #[account(token::mint = mint)]
+    pub token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(seeds = [b"mint-config", mint.key().as_ref()], bump)]
+    pub mint_config: Option<Account<'info, MintConfig>>,
+
+    #[account(
+        seeds = [b"settings"],
+        bump
+    )]
+    pub settings: Account<'info, ProgramSettings>,
+
+    /// Role held by a signer other than the settings authority
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+
+    #[account(
+        mut,
+        constraint = has_role(&settings, &authority.key(), &role_assignment, Role::Admin)
+            || mint_config.as_ref().is_some_and(|config| config.authority == authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseLockup<'info> {
+    #[account(
+        mut,
+        close = recipient,
+        seeds = [b"lockup", lockup.token_account.as_ref()],
+        bump
+    )]
+    pub lockup: Account<'info, Lockup>,
+
+    /// Locked token account, needed only when its owner releases the lockup
+    #[account(address = lockup.token_account)]
+    pub token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Receives the reclaimed rent
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+
+    #[account(seeds = [b"mint-config", lockup.mint.as_ref()], bump)]
+    pub mint_config: Option<Account<'info, MintConfig>>,
+
+    #[account(
+        seeds = [b"settings"],
+        bump
+    )]
+    pub settings: Account<'info, ProgramSettings>,
+
+    /// Role held by a signer other than the settings authority
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+
+    /// A manager, or the lockup owner once fully vested
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeJurisdictionPolicy<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 1 + 4 + 2 * MAX_POLICY_COUNTRIES + 1 + 8, // mint + mode + countries + require_accredited + updated_at
+        seeds = [b"jurisdiction", mint.key().as_ref()],
+        bump
+    )]
+    pub jurisdiction_policy: Account<'info, JurisdictionPolicy>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(seeds = [b"mint-config", mint.key().as_ref()], bump)]
+    pub mint_config: Option<Account<'info, MintConfig>>,
+
+    #[account(
+        seeds = [b"settings"],
+        bump
+    )]
+    pub settings: Account<'info, ProgramSettings>,
+
+    /// Role held by a signer other than the settings authority
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+
+    #[account(
+        mut,
+        constraint = has_role(&settings, &authority.key(), &role_assignment, Role::Admin)
+            || mint_config.as_ref().is_some_and(|config| config.authority == authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
-pub struct ValidateTransfer<'info> {
-    /// KYC account that stores approval status
+pub struct UpdateJurisdictionPolicy<'info> {
     #[account(
-        seeds = [b"kyc", owner.key().as_ref()], 
-        bump,
-        constraint = kyc.user == owner.key() @ ErrorCode::InvalidKycAccount
+        mut,
+        seeds = [b"jurisdiction", jurisdiction_policy.mint.as_ref()],
+        bump
     )]
-    pub kyc: Account<'info, KycData>,
-
-    /// The owner of the tokens being transferred
-    pub owner: Signer<'info>,
+    pub jurisdiction_policy: Account<'info, JurisdictionPolicy>,
 
-    /// The mint being transferred
-    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(seeds = [b"mint-config", jurisdiction_policy.mint.as_ref()], bump)]
+    pub mint_config: Option<Account<'info, MintConfig>>,
 
-    /// Source token account
-    pub source: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        seeds = [b"settings"],
+        bump
+    )]
+    pub settings: Account<'info, ProgramSettings>,
 
-    /// Destination token account
-    pub destination: InterfaceAccount<'info, TokenAccount>,
+    /// Role held by a signer other than the settings authority
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
 
-    /// Token program
-    pub token_program: Interface<'info, TokenInterface>,
+    #[account(
+        constraint = has_role(&settings, &authority.key(), &role_assignment, Role::Admin)
+            || mint_config.as_ref().is_some_and(|config| config.authority == authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct CreateKyc<'info> {
+#[instruction(wallet: Pubkey)]
+pub struct AddToAllowlist<'info> {
     #[account(
         init,
         payer = authority,
-        space = 8 + 32 + 1 + 8 + 8, // user + status + created_at + revoked_at
-        seeds = [b"kyc", user.key().as_ref()],
+        space = 8 + 32 + 32 + 8, // mint + wallet + added_at
+        seeds = [b"allow", mint.key().as_ref(), wallet.as_ref()],
         bump
     )]
-    pub kyc: Account<'info, KycData>,
+    pub allowlist_entry: Account<'info, AllowlistEntry>,
 
-    /// User who will be KYC approved
-    /// CHECK: Can be any account
-    pub user: AccountInfo<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(seeds = [b"mint-config", mint.key().as_ref()], bump)]
+    pub mint_config: Option<Account<'info, MintConfig>>,
 
     #[account(
-        mut,
         seeds = [b"settings"],
-        bump,
-        constraint = settings.authority == authority.key() @ ErrorCode::Unauthorized
+        bump
     )]
     pub settings: Account<'info, ProgramSettings>,
 
-    #[account(mut)]
+    /// Role held by a signer other than the settings authority
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+
+    #[account(
+        mut,
+        constraint = has_role(&settings, &authority.key(), &role_assignment, Role::Admin)
+            || mint_config.as_ref().is_some_and(|config| config.authority == authority.key()) @ ErrorCode::Unauthorized
+    )]
     pub authority: Signer<'info>,
 
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct RevokeKyc<'info> {
+pub struct RemoveFromAllowlist<'info> {
     #[account(
         mut,
-        seeds = [b"kyc", user.key().as_ref()],
-        bump,
-        constraint = kyc.user == user.key() @ ErrorCode::InvalidKycAccount
+        close = authority,
+        seeds = [b"allow", allowlist_entry.mint.as_ref(), allowlist_entry.wallet.as_ref()],
+        bump
     )]
-    pub kyc: Account<'info, KycData>,
+    pub allowlist_entry: Account<'info, AllowlistEntry>,
 
-    /// User whose KYC will be revoked
-    /// CHECK: Can be any account
-    pub user: AccountInfo<'info>,
+    #[account(seeds = [b"mint-config", allowlist_entry.mint.as_ref()], bump)]
+    pub mint_config: Option<Account<'info, MintConfig>>,
 
     #[account(
         seeds = [b"settings"],
-        bump,
-        constraint = settings.authority == authority.key() @ ErrorCode::Unauthorized
+        bump
     )]
     pub settings: Account<'info, ProgramSettings>,
 
+    /// Role held by a signer other than the settings authority
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+
+    #[account(
+        mut,
+        constraint = has_role(&settings, &authority.key(), &role_assignment, Role::Admin)
+            || mint_config.as_ref().is_some_and(|config| config.authority == authority.key()) @ ErrorCode::Unauthorized
+    )]
     pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct UpdateAuthority<'info> {
+pub struct CloseUserUsage<'info> {
     #[account(
         mut,
-        seeds = [b"settings"],
+        close = recipient,
+        seeds = [b"usage", user.key().as_ref(), mint.key().as_ref()],
         bump,
-        constraint = settings.authority == authority.key() @ ErrorCode::Unauthorized
+        constraint = user_usage.user == user.key() @ ErrorCode::Unauthorized
     )]
-    pub settings: Account<'info, ProgramSettings>,
+    pub user_usage: Account<'info, UserUsage>,
 
-    pub authority: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
 
-    /// CHECK: New authority can be any account
-    pub new_authority: AccountInfo<'info>,
+    /// CHECK: Receives the reclaimed rent
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+
+    pub user: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct Initialize<'info> {
+pub struct SetHookActive<'info> {
     #[account(
-        init,
-        payer = authority,
-        space = 8 + 32 + 8 + 1 + 8, // authority + created_at + is_active + updated_at
+        mut,
         seeds = [b"settings"],
         bump
     )]
     pub settings: Account<'info, ProgramSettings>,
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
+    /// Role held by a signer other than the settings authority
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
 
-    pub system_program: Program<'info, System>,
+    #[account(
+        constraint = has_role(&settings, &authority.key(), &role_assignment, Role::Pauser) @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
 pub struct SetTransferLimits<'info> {
     #[account(
-        init,
-        payer = authority,
-        space = 8 + 32 + 8 + 8 + 1 + 8, // mint + daily_limit + transaction_limit + is_active + updated_at
-        seeds = [b"limits", mint.key().as_ref()],
+        mut,
+        seeds = [b"mint-config", mint_config.mint.as_ref()],
         bump
     )]
-    pub mint_limits: Account<'info, MintLimits>,
-
-    pub mint: InterfaceAccount<'info, Mint>,
+    pub mint_config: Account<'info, MintConfig>,
 
     #[account(
         seeds = [b"settings"],
-        bump,
-        constraint = settings.authority == authority.key() @ ErrorCode::Unauthorized
+        bump
     )]
     pub settings: Account<'info, ProgramSettings>,
 
-    #[account(mut)]
+    /// Role held by a signer other than the settings authority
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+
+    #[account(
+        constraint = mint_config.authority == authority.key()
+            || has_role(&settings, &authority.key(), &role_assignment, Role::LimitsManager) @ ErrorCode::Unauthorized
+    )]
     pub authority: Signer<'info>,
+}
 
-    pub system_program: Program<'info, System>,
-    pub token_program: Interface<'info, TokenInterface>,
+#[derive(Accounts)]
+pub struct UpdateTransferLimits<'info> {
+    #[account(
+        mut,
+        seeds = [b"mint-config", mint_config.mint.as_ref()],
+        bump
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        seeds = [b"settings"],
+        bump
+    )]
+    pub settings: Account<'info, ProgramSettings>,
+
+    /// Role held by a signer other than the settings authority
+    pub role_assignment: Option<Account<'info, RoleAssignment>>,
+
+    #[account(
+        constraint = mint_config.authority == authority.key()
+            || has_role(&settings, &authority.key(), &role_assignment, Role::LimitsManager) @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
 pub struct CheckTransferLimits<'info> {
     #[account(
-        seeds = [b"limits", mint.key().as_ref()],
+        seeds = [b"kyc", user.key().as_ref()],
+        bump,
+        constraint = kyc.user == user.key() @ ErrorCode::InvalidKycAccount
+    )]
+    pub kyc: Account<'info, KycData>,
+
+    #[account(
+        seeds = [b"mint-config", mint.key().as_ref()],
+        bump,
+        constraint = mint_config.mint == mint.key() @ ErrorCode::InvalidMintLimits
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        seeds = [b"usage", user.key().as_ref(), mint.key().as_ref()],
         bump,
-        constraint = mint_limits.mint == mint.key() @ ErrorCode::InvalidMintLimits
+        constraint = user_usage.user == user.key() && user_usage.mint == mint.key() @ ErrorCode::InvalidUsageAccount
     )]
-    pub mint_limits: Account<'info, MintLimits>,
+    pub user_usage: Account<'info, UserUsage>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub user: Signer<'info>,
+}
 
+#[derive(Accounts)]
+pub struct InitUserUsage<'info> {
     #[account(
         init,
         payer = user,
-        space = 8 + 32 + 8 + 8, // user + daily_used + last_reset_day + last_transaction
+        space = USER_USAGE_SPACE,
         seeds = [b"usage", user.key().as_ref(), mint.key().as_ref()],
         bump
     )]
@@ -261,8 +1924,53 @@ pub struct CheckTransferLimits<'info> {
 pub struct KycData {
     pub user: Pubkey,
     pub status: u8, // 0 = revoked, 1 = approved
+    pub level: KycLevel,
     pub created_at: i64,
-    pub revoked_at: Option<i64>,
+    pub expires_at: i64,
+    pub revoked_at: Option<i64>, // most recent revocation
+    pub revocation_count: u32,
+    pub reinstatement_count: u32,
+    pub reinstated_at: Option<i64>, // most recent reinstatement
+    pub reinstatement_reason: u16,
+    pub country: [u8; 2], // ISO 3166-1 alpha-2, zeroes until set
+    pub accredited: bool,
+}
+
+impl KycData {
+    pub fn is_expired(&self, now: i64) -> bool {
+        now >= self.expires_at
+    }
+
+    pub fn is_valid(&self, now: i64) -> bool {
+        self.status == 1 && !self.is_expired(now)
+    }
+
+    fn revoke(&mut self, now: i64) {
+        self.status = 0; // Revoked
+        self.revoked_at = Some(now);
+        self.revocation_count = self.revocation_count.saturating_add(1);
+    }
+}
+
+// What remains of a closed KYC record: its revocation history, and a marker
+// that keeps create_kyc from issuing the user a fresh one.
+#[account]
+pub struct KycTombstone {
+    pub user: Pubkey,
+    pub revocation_count: u32,
+    pub reinstatement_count: u32,
+    pub closed_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum KycLevel {
+    Basic,
+    Enhanced,
+    Institutional,
+}
+
+impl KycLevel {
+    pub const ALL: [KycLevel; 3] = [KycLevel::Basic, KycLevel::Enhanced, KycLevel::Institutional];
 }
 
 #[account]
@@ -271,29 +1979,378 @@ pub struct ProgramSettings {
     pub created_at: i64,
     pub is_active: bool,
     pub updated_at: i64,
+    pub pending_authority: Option<Pubkey>,
 }
 
+// The settings authority implicitly holds every role
 #[account]
+pub struct RoleAssignment {
+    pub member: Pubkey,
+    pub role: Role,
+    pub granted_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Role {
+    Admin,         // registries, mint config and role management
+    KycOperator,   // create, renew and revoke KYC
+    LimitsManager, // transfer limits
+    Pauser,        // pause and resume transfers
+}
+
+// One row of a mint's transfer limits, held in its MintConfig per KYC tier
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug)]
 pub struct MintLimits {
-    pub mint: Pubkey,
-    pub daily_limit: u64,
+    pub daily_limit: u64, // rolling 24 hours
     pub transaction_limit: u64,
+    pub weekly_limit: Option<u64>, // rolling 7 days
+    pub monthly_limit: Option<u64>, // rolling 30 days
+    pub max_daily_transfers: Option<u32>, // rolling 24 hours, enforced by the hook
+    pub cooldown_seconds: Option<u32>, // minimum gap between transfers, enforced by the hook
     pub is_active: bool,
     pub updated_at: i64,
 }
 
+impl MintLimits {
+    pub fn limits(&self) -> TransferLimits {
+        TransferLimits {
+            daily_limit: self.daily_limit,
+            transaction_limit: self.transaction_limit,
+            weekly_limit: self.weekly_limit,
+            monthly_limit: self.monthly_limit,
+            max_daily_transfers: self.max_daily_transfers,
+            cooldown_seconds: self.cooldown_seconds,
+        }
+    }
+
+    fn apply(&mut self, limits: &TransferLimits) {
+        self.daily_limit = limits.daily_limit;
+        self.transaction_limit = limits.transaction_limit;
+        self.weekly_limit = limits.weekly_limit;
+        self.monthly_limit = limits.monthly_limit;
+        self.max_daily_transfers = limits.max_daily_transfers;
+        self.cooldown_seconds = limits.cooldown_seconds;
+    }
+}
+
+// Per-mint policy, so one hook deployment can govern several tokens. The mint's
+// own authority manages it alongside the program authority and admins.
+#[account]
+pub struct MintConfig {
+    pub mint: Pubkey,
+    pub authority: Pubkey,
+    pub require_sender_kyc: bool,
+    pub require_recipient_kyc: bool,
+    pub limits_enabled: bool,
+    /// Cap on each non-exempt token account, not on each owner
+    pub max_balance: Option<u64>,
+    pub allowlist_only: bool,
+    pub is_paused: bool,
+    pub updated_at: i64,
+    pub tier_limits: [MintLimits; 3], // indexed by KycLevel
+}
+
+impl MintConfig {
+    pub fn limits_for(&self, level: KycLevel) -> &MintLimits {
+        &self.tier_limits[level as usize]
+    }
+
+    fn limits_for_mut(&mut self, level: KycLevel) -> &mut MintLimits {
+        &mut self.tier_limits[level as usize]
+    }
+
+    pub fn policy(&self) -> MintPolicy {
+        MintPolicy {
+            require_sender_kyc: self.require_sender_kyc,
+            require_recipient_kyc: self.require_recipient_kyc,
+            limits_enabled: self.limits_enabled,
+            max_balance: self.max_balance,
+            allowlist_only: self.allowlist_only,
+        }
+    }
+
+    fn apply(&mut self, policy: &MintPolicy) {
+        self.require_sender_kyc = policy.require_sender_kyc;
+        self.require_recipient_kyc = policy.require_recipient_kyc;
+        self.limits_enabled = policy.limits_enabled;
+        self.max_balance = policy.max_balance;
+        self.allowlist_only = policy.allowlist_only;
+    }
+}
+
+// Tokens of `mint` that `token_account` must keep on hand: all of `amount` until
+// the cliff, then vesting linearly from `start_at` so nothing is locked at
+// `end_at`. Keyed by token account rather than owner, so the lockup stays with
+// the tokens if the account changes hands and leaves the owner's other
+// accounts alone.
+#[account]
+pub struct Lockup {
+    pub token_account: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub start_at: i64,
+    pub cliff_at: i64,
+    pub end_at: i64,
+    pub created_at: i64,
+}
+
+impl Lockup {
+    pub fn locked_amount(&self, now: i64) -> u64 {
+        if now < self.cliff_at {
+            return self.amount;
+        }
+        if now >= self.end_at {
+            return 0;
+        }
+        let elapsed = (now - self.start_at) as u128;
+        let duration = (self.end_at - self.start_at) as u128;
+        let vested = self.amount as u128 * elapsed / duration;
+        self.amount - vested as u64
+    }
+}
+
+// Countries a mint may or may not be transferred to or from, matched against
+// the KYC records of both the source and destination owners.
+#[account]
+pub struct JurisdictionPolicy {
+    pub mint: Pubkey,
+    pub mode: JurisdictionMode,
+    pub countries: Vec<[u8; 2]>,
+    pub require_accredited: bool,
+    pub updated_at: i64,
+}
+
+impl JurisdictionPolicy {
+    // Wallets without a KYC record have no known country, so they pass a
+    // blocklist but never an allowlist or an accreditation requirement.
+    pub fn permits(&self, kyc: Option<&KycData>) -> bool {
+        let Some(kyc) = kyc else {
+            return self.mode == JurisdictionMode::Blocklist && !self.require_accredited;
+        };
+        if self.require_accredited && !kyc.accredited {
+            return false;
+        }
+        let listed = self.countries.contains(&kyc.country);
+        match self.mode {
+            JurisdictionMode::Allowlist => listed,
+            JurisdictionMode::Blocklist => !listed,
+        }
+    }
+
+    fn apply(&mut self, rules: JurisdictionRules) -> Result<()> {
+        require!(rules.countries.len() <= MAX_POLICY_COUNTRIES, ErrorCode::TooManyCountries);
+        require!(rules.countries.iter().all(is_country_code), ErrorCode::InvalidCountryCode);
+        self.mode = rules.mode;
+        self.countries = rules.countries;
+        self.require_accredited = rules.require_accredited;
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum JurisdictionMode {
+    Blocklist,
+    Allowlist,
+}
+
+fn is_country_code(code: &[u8; 2]) -> bool {
+    code.iter().all(u8::is_ascii_uppercase)
+}
+
+// Token account or wallet that transfers may move through without KYC or
+// limit checks, e.g. an AMM vault or a treasury.
+#[account]
+pub struct ExemptAccount {
+    pub address: Pubkey,
+    pub added_at: i64,
+}
+
+#[account]
+pub struct DenylistEntry {
+    pub wallet: Pubkey,
+    pub reason: u16, // compliance reason code, opaque to the program
+    pub added_at: i64,
+}
+
+// An owner allowed to hold `mint` while its config is in allowlist-only mode
+#[account]
+pub struct AllowlistEntry {
+    pub mint: Pubkey,
+    pub wallet: Pubkey,
+    pub added_at: i64,
+}
+
 #[account]
 pub struct UserUsage {
     pub user: Pubkey,
-    pub daily_used: u64,
-    pub last_reset_day: i64,
+    pub mint: Pubkey,
+    pub hourly_used: [u64; HOURLY_BUCKETS], // indexed by hour since epoch, mod 24
+    pub hourly_transfers: [u32; HOURLY_BUCKETS], // transfers seen by the hook, same indexing
+    pub current_hour: i64,
+    pub daily_used: [u64; DAILY_BUCKETS], // indexed by day since epoch, mod 30
+    pub current_day: i64,
     pub last_transaction: i64,
 }
 
+impl UserUsage {
+    // Moves both windows up to `now`, clearing buckets that fell out of them.
+    fn roll(&mut self, now: i64) {
+        let hour = now / 3600;
+        for index in stale_buckets(self.current_hour, hour, HOURLY_BUCKETS) {
+            self.hourly_used[index] = 0;
+            self.hourly_transfers[index] = 0;
+        }
+        self.current_hour = self.current_hour.max(hour);
+
+        let day = now / 86400;
+        for index in stale_buckets(self.current_day, day, DAILY_BUCKETS) {
+            self.daily_used[index] = 0;
+        }
+        self.current_day = self.current_day.max(day);
+    }
+
+    pub fn last_24h(&self) -> Option<u64> {
+        self.hourly_used.iter().try_fold(0u64, |sum, used| sum.checked_add(*used))
+    }
+
+    pub fn transfers_last_24h(&self) -> u32 {
+        self.hourly_transfers.iter().fold(0u32, |sum, count| sum.saturating_add(*count))
+    }
+
+    // Total over the most recent `days` days, today included.
+    pub fn last_days(&self, days: usize) -> Option<u64> {
+        (0..days.min(DAILY_BUCKETS)).try_fold(0u64, |sum, offset| {
+            let day = (self.current_day - offset as i64).rem_euclid(DAILY_BUCKETS as i64);
+            sum.checked_add(self.daily_used[day as usize])
+        })
+    }
+
+    // Fails if sending `amount` at `now` would break any of `limits`; expects the
+    // windows to have been rolled up to `now` already.
+    fn check(&self, limits: &MintLimits, amount: u64, now: i64) -> Result<()> {
+        if let Some(cooldown) = limits.cooldown_seconds {
+            require!(
+                now.saturating_sub(self.last_transaction) >= i64::from(cooldown),
+                ErrorCode::TransferCooldownActive
+            );
+        }
+        if let Some(max_transfers) = limits.max_daily_transfers {
+            require!(
+                self.transfers_last_24h() < max_transfers,
+                ErrorCode::TransferCountExceeded
+            );
+        }
+
+        // The last 24 hours, then the optional 7- and 30-day caps
+        let used = |total: Option<u64>| {
+            total
+                .and_then(|total| total.checked_add(amount))
+                .ok_or(ErrorCode::MathOverflow)
+        };
+        require!(
+            used(self.last_24h())? <= limits.daily_limit,
+            ErrorCode::DailyLimitExceeded
+        );
+        if let Some(weekly_limit) = limits.weekly_limit {
+            require!(
+                used(self.last_days(DAYS_PER_WEEK))? <= weekly_limit,
+                ErrorCode::WeeklyLimitExceeded
+            );
+        }
+        if let Some(monthly_limit) = limits.monthly_limit {
+            require!(
+                used(self.last_days(DAILY_BUCKETS))? <= monthly_limit,
+                ErrorCode::MonthlyLimitExceeded
+            );
+        }
+        Ok(())
+    }
+
+    fn record(&mut self, amount: u64) -> Result<()> {
+        let hour = self.current_hour.rem_euclid(HOURLY_BUCKETS as i64) as usize;
+        let day = self.current_day.rem_euclid(DAILY_BUCKETS as i64) as usize;
+        self.hourly_used[hour] = self.hourly_used[hour].checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        self.daily_used[day] = self.daily_used[day].checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    fn record_transfer(&mut self, now: i64) {
+        let hour = self.current_hour.rem_euclid(HOURLY_BUCKETS as i64) as usize;
+        self.hourly_transfers[hour] = self.hourly_transfers[hour].saturating_add(1);
+        self.last_transaction = now;
+    }
+}
+
+// Ring buffer slots to clear when moving from period `current` to `period`:
+// every slot after `current` up to and including `period`, or the whole
+// buffer once a full window has passed. A clock that moves backwards clears
+// nothing.
+fn stale_buckets(current: i64, period: i64, len: usize) -> impl Iterator<Item = usize> {
+    let len = len as i64;
+    let elapsed = (period - current).clamp(0, len);
+    (1..=elapsed).map(move |step| (current + step).rem_euclid(len) as usize)
+}
+
+#[event]
+pub struct TransferLimitsUpdated {
+    pub mint: Pubkey,
+    pub level: KycLevel,
+    pub previous: TransferLimits,
+    pub was_active: bool,
+    pub current: TransferLimits,
+    pub is_active: bool,
+    pub updated_by: Pubkey,
+    pub updated_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct LockupSchedule {
+    pub amount: u64,
+    pub start_at: i64,
+    pub cliff_at: i64,
+    pub end_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct JurisdictionRules {
+    pub mode: JurisdictionMode,
+    pub countries: Vec<[u8; 2]>,
+    pub require_accredited: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct MintPolicy {
+    pub require_sender_kyc: bool,
+    pub require_recipient_kyc: bool,
+    pub limits_enabled: bool,
+    /// Most any non-exempt token account may hold, checked per account
+    pub max_balance: Option<u64>,
+    pub allowlist_only: bool, // only allowlisted owners may send or receive
+}
+
+// What the hook enforces for a mint that has no MintConfig
+impl Default for MintPolicy {
+    fn default() -> Self {
+        MintPolicy {
+            require_sender_kyc: true,
+            require_recipient_kyc: false,
+            // Without a MintConfig there are no limit rows, so this only matters
+            // as the starting policy for new configs: their rows apply once set.
+            limits_enabled: true,
+            max_balance: None,
+            allowlist_only: false,
+        }
+    }
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize)]
 pub struct TransferLimits {
     pub daily_limit: u64,
     pub transaction_limit: u64,
+    pub weekly_limit: Option<u64>,
+    pub monthly_limit: Option<u64>,
+    pub max_daily_transfers: Option<u32>,
+    pub cooldown_seconds: Option<u32>,
 }
 
 #[error_code]
@@ -310,4 +2367,72 @@ pub enum ErrorCode {
     DailyLimitExceeded,
     #[msg("Invalid mint limits")]
     InvalidMintLimits,
+    #[msg("Hook can only be invoked during a token transfer")]
+    NotTransferring,
+    #[msg("KYC approval has expired")]
+    KycExpired,
+    #[msg("KYC expiry must be in the future")]
+    InvalidExpiry,
+    #[msg("KYC has been revoked")]
+    KycRevoked,
+    #[msg("Account is not owned by the hook program")]
+    InvalidAccountOwner,
+    #[msg("Recipient KYC validation failed")]
+    RecipientKycFailed,
+    #[msg("Source wallet is denylisted")]
+    SourceDenylisted,
+    #[msg("Destination wallet is denylisted")]
+    DestinationDenylisted,
+    #[msg("No authority transfer is pending")]
+    NoPendingAuthority,
+    #[msg("Transfers are paused")]
+    HookPaused,
+    #[msg("Batch accounts must be passed as (user, kyc) pairs")]
+    InvalidBatch,
+    #[msg("Only revoked KYC records can be closed")]
+    KycNotRevoked,
+    #[msg("Usage account has been used too recently to close")]
+    UsageStillActive,
+    #[msg("A non-zero reason code is required")]
+    MissingReasonCode,
+    #[msg("Usage account does not belong to this user and mint")]
+    InvalidUsageAccount,
+    #[msg("Math overflow")]
+    MathOverflow,
+    #[msg("Weekly transfer limit exceeded")]
+    WeeklyLimitExceeded,
+    #[msg("Monthly transfer limit exceeded")]
+    MonthlyLimitExceeded,
+    #[msg("Usage account must be initialized before transferring this mint")]
+    UsageNotInitialized,
+    #[msg("Transfer cooldown has not elapsed")]
+    TransferCooldownActive,
+    #[msg("Transfer count limit exceeded")]
+    TransferCountExceeded,
+    #[msg("Transfers of this mint are paused")]
+    MintPaused,
+    #[msg("Country codes must be two uppercase ISO 3166-1 letters")]
+    InvalidCountryCode,
+    #[msg("Jurisdiction policy lists too many countries")]
+    TooManyCountries,
+    #[msg("Source owner's jurisdiction is not permitted for this mint")]
+    SourceJurisdictionBlocked,
+    #[msg("Destination owner's jurisdiction is not permitted for this mint")]
+    DestinationJurisdictionBlocked,
+    #[msg("Lockup needs a positive amount and start <= cliff <= end, with start < end")]
+    InvalidLockupSchedule,
+    #[msg("Transfer would move locked tokens")]
+    LockupViolation,
+    #[msg("Lockup has not fully vested")]
+    LockupStillActive,
+    #[msg("Transfer limits already set for this tier")]
+    TransferLimitsAlreadySet,
+    #[msg("Transfer would exceed the destination account's maximum balance")]
+    MaxBalanceExceeded,
+    #[msg("Source wallet is not on the mint's allowlist")]
+    SourceNotAllowlisted,
+    #[msg("Destination wallet is not on the mint's allowlist")]
+    DestinationNotAllowlisted,
+    #[msg("KYC record was closed and cannot be recreated")]
+    KycRecordClosed,
 }
@@ -0,0 +1,2317 @@
+use anchor_lang::{
+    solana_program::{clock::Clock, instruction::Instruction},
+    AccountDeserialize, InstructionData, ToAccountMetas,
+};
+use hook::{
+    JurisdictionMode, JurisdictionRules, KycData, KycLevel, KycTombstone, LockupSchedule,
+    MintConfig, MintPolicy, ProgramSettings, Role, TransferLimits, UserUsage,
+};
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, InstructionError},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction, system_program,
+    transaction::{Transaction, TransactionError},
+};
+use spl_token_2022::{
+    extension::{transfer_hook, ExtensionType},
+    instruction::AuthorityType,
+    state::{Account as TokenAccount, Mint},
+};
+
+mod support;
+
+const DECIMALS: u8 = 9;
+const TOKENS: u64 = 1_000_000_000;
+const DAY: i64 = 86_400;
+
+// Anchor's generated entrypoint ties the accounts slice to the account lifetime,
+// which the program-test processor signature cannot express.
+fn hook_processor(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    hook::entry(program_id, accounts, data)
+}
+
+struct Env {
+    ctx: ProgramTestContext,
+    mint: Pubkey,
+}
+
+struct Holder {
+    keypair: Keypair,
+    token_account: Pubkey,
+}
+
+impl Holder {
+    fn owner(&self) -> Pubkey {
+        self.keypair.pubkey()
+    }
+}
+
+fn settings_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"settings"], &hook::ID).0
+}
+
+fn kyc_address(user: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"kyc", user.as_ref()], &hook::ID).0
+}
+
+fn kyc_tombstone_address(user: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"kyc-tombstone", user.as_ref()], &hook::ID).0
+}
+
+fn extra_account_metas_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"extra-account-metas", mint.as_ref()], &hook::ID).0
+}
+
+fn mint_config_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"mint-config", mint.as_ref()], &hook::ID).0
+}
+
+fn exempt_address(address: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"exempt", address.as_ref()], &hook::ID).0
+}
+
+fn deny_address(wallet: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"deny", wallet.as_ref()], &hook::ID).0
+}
+
+fn role_address(member: &Pubkey, role: Role) -> Pubkey {
+    Pubkey::find_program_address(&[b"role", member.as_ref(), &[role as u8]], &hook::ID).0
+}
+
+fn usage_address(user: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"usage", user.as_ref(), mint.as_ref()], &hook::ID).0
+}
+
+fn lockup_address(token_account: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"lockup", token_account.as_ref()], &hook::ID).0
+}
+
+fn jurisdiction_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"jurisdiction", mint.as_ref()], &hook::ID).0
+}
+
+fn allowlist_address(mint: &Pubkey, wallet: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"allow", mint.as_ref(), wallet.as_ref()], &hook::ID).0
+}
+
+async fn send(
+    ctx: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let blockhash = ctx.banks_client.get_latest_blockhash().await.unwrap();
+    let mut all_signers = vec![&ctx.payer];
+    all_signers.extend_from_slice(signers);
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&ctx.payer.pubkey()),
+        &all_signers,
+        blockhash,
+    );
+    ctx.banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+fn assert_custom_error(result: Result<(), TransactionError>, code: u32) {
+    match result {
+        Err(TransactionError::InstructionError(_, InstructionError::Custom(actual))) => {
+            assert_eq!(actual, code)
+        }
+        other => panic!("expected custom error {code}, got {other:?}"),
+    }
+}
+
+async fn now(ctx: &mut ProgramTestContext) -> i64 {
+    ctx.banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap()
+        .unix_timestamp
+}
+
+async fn set_time(ctx: &mut ProgramTestContext, unix_timestamp: i64) {
+    let mut clock = ctx.banks_client.get_sysvar::<Clock>().await.unwrap();
+    clock.unix_timestamp = unix_timestamp;
+    ctx.set_sysvar(&clock);
+}
+
+fn hook_ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: hook::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+fn create_kyc_ix(authority: Pubkey, user: Pubkey, level: KycLevel, expires_at: i64) -> Instruction {
+    hook_ix(
+        hook::accounts::CreateKyc {
+            kyc: kyc_address(&user),
+            user,
+            kyc_tombstone: kyc_tombstone_address(&user),
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+            system_program: system_program::ID,
+        },
+        hook::instruction::CreateKyc { level, expires_at },
+    )
+}
+
+fn revoke_kyc_ix(authority: Pubkey, user: Pubkey) -> Instruction {
+    hook_ix(
+        hook::accounts::RevokeKyc {
+            kyc: kyc_address(&user),
+            user,
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+        },
+        hook::instruction::RevokeKyc {},
+    )
+}
+
+fn renew_kyc_ix(authority: Pubkey, user: Pubkey, level: KycLevel, expires_at: i64) -> Instruction {
+    hook_ix(
+        hook::accounts::RenewKyc {
+            kyc: kyc_address(&user),
+            user,
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+        },
+        hook::instruction::RenewKyc { level, expires_at },
+    )
+}
+
+fn set_kyc_jurisdiction_ix(
+    authority: Pubkey,
+    user: Pubkey,
+    country: &[u8; 2],
+    accredited: bool,
+) -> Instruction {
+    hook_ix(
+        hook::accounts::SetKycJurisdiction {
+            kyc: kyc_address(&user),
+            user,
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+        },
+        hook::instruction::SetKycJurisdiction {
+            country: *country,
+            accredited,
+        },
+    )
+}
+
+fn reinstate_kyc_ix(authority: Pubkey, user: Pubkey, reason: u16, expires_at: i64) -> Instruction {
+    hook_ix(
+        hook::accounts::ReinstateKyc {
+            kyc: kyc_address(&user),
+            user,
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+        },
+        hook::instruction::ReinstateKyc { reason, expires_at },
+    )
+}
+
+fn set_transfer_limits_ix(
+    authority: Pubkey,
+    mint: Pubkey,
+    level: KycLevel,
+    daily_limit: u64,
+    transaction_limit: u64,
+) -> Instruction {
+    hook_ix(
+        hook::accounts::SetTransferLimits {
+            mint_config: mint_config_address(&mint),
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+        },
+        hook::instruction::SetTransferLimits {
+            level,
+            limits: TransferLimits {
+                daily_limit,
+                transaction_limit,
+                weekly_limit: None,
+                monthly_limit: None,
+                max_daily_transfers: None,
+                cooldown_seconds: None,
+            },
+        },
+    )
+}
+
+fn update_transfer_limits_ix(
+    authority: Pubkey,
+    mint: Pubkey,
+    level: KycLevel,
+    daily_limit: u64,
+    transaction_limit: u64,
+    is_active: bool,
+) -> Instruction {
+    hook_ix(
+        hook::accounts::UpdateTransferLimits {
+            mint_config: mint_config_address(&mint),
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+        },
+        hook::instruction::UpdateTransferLimits {
+            level,
+            limits: TransferLimits {
+                daily_limit,
+                transaction_limit,
+                weekly_limit: None,
+                monthly_limit: None,
+                max_daily_transfers: None,
+                cooldown_seconds: None,
+            },
+            is_active,
+        },
+    )
+}
+
+fn init_user_usage_ix(user: Pubkey, mint: Pubkey) -> Instruction {
+    hook_ix(
+        hook::accounts::InitUserUsage {
+            user_usage: usage_address(&user, &mint),
+            mint,
+            user,
+            system_program: system_program::ID,
+        },
+        hook::instruction::InitUserUsage {},
+    )
+}
+
+fn check_transfer_limits_ix(user: Pubkey, mint: Pubkey, amount: u64) -> Instruction {
+    hook_ix(
+        hook::accounts::CheckTransferLimits {
+            kyc: kyc_address(&user),
+            mint_config: mint_config_address(&mint),
+            user_usage: usage_address(&user, &mint),
+            mint,
+            user,
+        },
+        hook::instruction::CheckTransferLimits { amount },
+    )
+}
+
+fn initialize_mint_config_ix(
+    authority: Pubkey,
+    mint: Pubkey,
+    require_recipient_kyc: bool,
+) -> Instruction {
+    hook_ix(
+        hook::accounts::InitializeMintConfig {
+            mint_config: mint_config_address(&mint),
+            mint,
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+            system_program: system_program::ID,
+        },
+        hook::instruction::InitializeMintConfig {
+            authority,
+            policy: MintPolicy {
+                require_recipient_kyc,
+                ..MintPolicy::default()
+            },
+        },
+    )
+}
+
+fn create_lockup_ix(
+    authority: Pubkey,
+    mint: Pubkey,
+    token_account: Pubkey,
+    schedule: LockupSchedule,
+) -> Instruction {
+    hook_ix(
+        hook::accounts::CreateLockup {
+            lockup: lockup_address(&token_account),
+            token_account,
+            mint,
+            mint_config: None,
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+            system_program: system_program::ID,
+        },
+        hook::instruction::CreateLockup { schedule },
+    )
+}
+
+fn release_lockup_ix(authority: Pubkey, token_account: Pubkey) -> Instruction {
+    hook_ix(
+        hook::accounts::ReleaseLockup {
+            lockup: lockup_address(&token_account),
+            token_account: Some(token_account),
+            recipient: authority,
+            mint_config: None,
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+        },
+        hook::instruction::ReleaseLockup {},
+    )
+}
+
+fn add_to_allowlist_ix(authority: Pubkey, mint: Pubkey, wallet: Pubkey) -> Instruction {
+    hook_ix(
+        hook::accounts::AddToAllowlist {
+            allowlist_entry: allowlist_address(&mint, &wallet),
+            mint,
+            mint_config: None,
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+            system_program: system_program::ID,
+        },
+        hook::instruction::AddToAllowlist { wallet },
+    )
+}
+
+fn remove_from_allowlist_ix(authority: Pubkey, mint: Pubkey, wallet: Pubkey) -> Instruction {
+    hook_ix(
+        hook::accounts::RemoveFromAllowlist {
+            allowlist_entry: allowlist_address(&mint, &wallet),
+            mint_config: None,
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+        },
+        hook::instruction::RemoveFromAllowlist {},
+    )
+}
+
+fn jurisdiction_rules(
+    mode: JurisdictionMode,
+    countries: &[&[u8; 2]],
+    require_accredited: bool,
+) -> JurisdictionRules {
+    JurisdictionRules {
+        mode,
+        countries: countries.iter().map(|country| **country).collect(),
+        require_accredited,
+    }
+}
+
+fn initialize_jurisdiction_policy_ix(
+    authority: Pubkey,
+    mint: Pubkey,
+    rules: JurisdictionRules,
+) -> Instruction {
+    hook_ix(
+        hook::accounts::InitializeJurisdictionPolicy {
+            jurisdiction_policy: jurisdiction_address(&mint),
+            mint,
+            mint_config: None,
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+            system_program: system_program::ID,
+        },
+        hook::instruction::InitializeJurisdictionPolicy { rules },
+    )
+}
+
+fn update_jurisdiction_policy_ix(
+    authority: Pubkey,
+    mint: Pubkey,
+    rules: JurisdictionRules,
+) -> Instruction {
+    hook_ix(
+        hook::accounts::UpdateJurisdictionPolicy {
+            jurisdiction_policy: jurisdiction_address(&mint),
+            mint_config: None,
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+        },
+        hook::instruction::UpdateJurisdictionPolicy { rules },
+    )
+}
+
+fn update_mint_config_ix(
+    authority: Pubkey,
+    mint: Pubkey,
+    data: impl InstructionData,
+) -> Instruction {
+    hook_ix(
+        hook::accounts::UpdateMintConfig {
+            mint_config: mint_config_address(&mint),
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+        },
+        data,
+    )
+}
+
+fn add_exempt_account_ix(authority: Pubkey, address: Pubkey) -> Instruction {
+    hook_ix(
+        hook::accounts::AddExemptAccount {
+            exempt_account: exempt_address(&address),
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+            system_program: system_program::ID,
+        },
+        hook::instruction::AddExemptAccount { address },
+    )
+}
+
+fn remove_exempt_account_ix(authority: Pubkey, address: Pubkey) -> Instruction {
+    hook_ix(
+        hook::accounts::RemoveExemptAccount {
+            exempt_account: exempt_address(&address),
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+        },
+        hook::instruction::RemoveExemptAccount {},
+    )
+}
+
+fn add_to_denylist_ix(authority: Pubkey, wallet: Pubkey, reason: u16) -> Instruction {
+    hook_ix(
+        hook::accounts::AddToDenylist {
+            denylist_entry: deny_address(&wallet),
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+            system_program: system_program::ID,
+        },
+        hook::instruction::AddToDenylist { wallet, reason },
+    )
+}
+
+fn remove_from_denylist_ix(authority: Pubkey, wallet: Pubkey) -> Instruction {
+    hook_ix(
+        hook::accounts::RemoveFromDenylist {
+            denylist_entry: deny_address(&wallet),
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+        },
+        hook::instruction::RemoveFromDenylist {},
+    )
+}
+
+fn grant_role_ix(authority: Pubkey, member: Pubkey, role: Role) -> Instruction {
+    hook_ix(
+        hook::accounts::GrantRole {
+            role_assignment: role_address(&member, role),
+            settings: settings_address(),
+            admin_role: None,
+            authority,
+            system_program: system_program::ID,
+        },
+        hook::instruction::GrantRole { member, role },
+    )
+}
+
+// Instruction signed by `signer` through a role it was granted rather than as
+// the settings authority.
+fn with_role(mut ix: Instruction, signer: Pubkey, role: Role) -> Instruction {
+    let role_assignment = role_address(&signer, role);
+    for meta in ix.accounts.iter_mut() {
+        if meta.pubkey == hook::ID {
+            meta.pubkey = role_assignment;
+            break;
+        }
+    }
+    ix
+}
+
+fn propose_authority_ix(authority: Pubkey, new_authority: Pubkey) -> Instruction {
+    hook_ix(
+        hook::accounts::ProposeAuthority {
+            settings: settings_address(),
+            authority,
+        },
+        hook::instruction::ProposeAuthority { new_authority },
+    )
+}
+
+fn accept_authority_ix(new_authority: Pubkey) -> Instruction {
+    hook_ix(
+        hook::accounts::AcceptAuthority {
+            settings: settings_address(),
+            new_authority,
+        },
+        hook::instruction::AcceptAuthority {},
+    )
+}
+
+fn set_hook_active_ix(authority: Pubkey, active: bool) -> Instruction {
+    hook_ix(
+        hook::accounts::SetHookActive {
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+        },
+        hook::instruction::SetHookActive { active },
+    )
+}
+
+// Hook program initialized with the payer as authority, and a Token-2022 mint
+// pointing at it with its extra account metas in place.
+async fn setup() -> Env {
+    // `cargo test-sbf` runs the built hook.so instead of the native entrypoint
+    let mut program_test = ProgramTest::default();
+    program_test.prefer_bpf(cfg!(feature = "test-sbf"));
+    program_test.add_program("hook", hook::ID, processor!(hook_processor));
+    let mut ctx = program_test.start_with_context().await;
+    let authority = ctx.payer.pubkey();
+
+    let ix = hook_ix(
+        hook::accounts::Initialize {
+            settings: settings_address(),
+            authority,
+            system_program: system_program::ID,
+        },
+        hook::instruction::Initialize {},
+    );
+    send(&mut ctx, &[ix], &[]).await.unwrap();
+
+    let mint = Keypair::new();
+    let space =
+        ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::TransferHook]).unwrap();
+    let rent = ctx.banks_client.get_rent().await.unwrap();
+    send(
+        &mut ctx,
+        &[
+            system_instruction::create_account(
+                &authority,
+                &mint.pubkey(),
+                rent.minimum_balance(space),
+                space as u64,
+                &spl_token_2022::id(),
+            ),
+            transfer_hook::instruction::initialize(
+                &spl_token_2022::id(),
+                &mint.pubkey(),
+                Some(authority),
+                Some(hook::ID),
+            )
+            .unwrap(),
+            spl_token_2022::instruction::initialize_mint2(
+                &spl_token_2022::id(),
+                &mint.pubkey(),
+                &authority,
+                None,
+                DECIMALS,
+            )
+            .unwrap(),
+        ],
+        &[&mint],
+    )
+    .await
+    .unwrap();
+
+    let ix = hook_ix(
+        hook::accounts::InitializeExtraAccountMetaList {
+            extra_account_meta_list: extra_account_metas_address(&mint.pubkey()),
+            mint: mint.pubkey(),
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+            system_program: system_program::ID,
+        },
+        hook::instruction::InitializeExtraAccountMetaList {},
+    );
+    send(&mut ctx, &[ix], &[]).await.unwrap();
+
+    Env {
+        ctx,
+        mint: mint.pubkey(),
+    }
+}
+
+impl Env {
+    fn authority(&self) -> Pubkey {
+        self.ctx.payer.pubkey()
+    }
+
+    // A wallet with a hook-enabled token account holding `tokens`, approved at
+    // `kyc` for a year when given.
+    async fn create_holder(&mut self, tokens: u64, kyc: Option<KycLevel>) -> Holder {
+        self.open_account(Keypair::new(), tokens, kyc).await
+    }
+
+    // Opens another token account for `keypair`, which may already hold one.
+    async fn open_account(
+        &mut self,
+        keypair: Keypair,
+        tokens: u64,
+        kyc: Option<KycLevel>,
+    ) -> Holder {
+        let token_account = Keypair::new();
+        let authority = self.authority();
+        let space = ExtensionType::try_calculate_account_len::<TokenAccount>(&[
+            ExtensionType::TransferHookAccount,
+        ])
+        .unwrap();
+        let rent = self.ctx.banks_client.get_rent().await.unwrap();
+
+        let mut instructions = vec![
+            system_instruction::create_account(
+                &authority,
+                &token_account.pubkey(),
+                rent.minimum_balance(space),
+                space as u64,
+                &spl_token_2022::id(),
+            ),
+            spl_token_2022::instruction::initialize_account3(
+                &spl_token_2022::id(),
+                &token_account.pubkey(),
+                &self.mint,
+                &keypair.pubkey(),
+            )
+            .unwrap(),
+        ];
+        if tokens > 0 {
+            instructions.push(
+                spl_token_2022::instruction::mint_to(
+                    &spl_token_2022::id(),
+                    &self.mint,
+                    &token_account.pubkey(),
+                    &authority,
+                    &[],
+                    tokens,
+                )
+                .unwrap(),
+            );
+        }
+        if let Some(level) = kyc {
+            let expires_at = now(&mut self.ctx).await + 365 * DAY;
+            instructions.push(create_kyc_ix(
+                authority,
+                keypair.pubkey(),
+                level,
+                expires_at,
+            ));
+        }
+        send(&mut self.ctx, &instructions, &[&token_account])
+            .await
+            .unwrap();
+
+        Holder {
+            keypair,
+            token_account: token_account.pubkey(),
+        }
+    }
+
+    // Funds `holder` and opens their usage account, which the hook needs once
+    // their tier has limits.
+    async fn init_usage(&mut self, holder: &Holder) {
+        let ixs = [
+            system_instruction::transfer(&self.authority(), &holder.owner(), 1_000_000_000),
+            init_user_usage_ix(holder.owner(), self.mint),
+        ];
+        send(&mut self.ctx, &ixs, &[&holder.keypair]).await.unwrap();
+    }
+
+    async fn kyc(&mut self, user: &Pubkey) -> KycData {
+        let account = self
+            .ctx
+            .banks_client
+            .get_account(kyc_address(user))
+            .await
+            .unwrap()
+            .unwrap();
+        KycData::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    async fn settings(&mut self) -> ProgramSettings {
+        let data = self.account_data(settings_address()).await.unwrap();
+        ProgramSettings::try_deserialize(&mut data.as_slice()).unwrap()
+    }
+
+    async fn lamports(&mut self, address: Pubkey) -> u64 {
+        self.ctx.banks_client.get_balance(address).await.unwrap()
+    }
+
+    async fn account_data(&mut self, address: Pubkey) -> Option<Vec<u8>> {
+        self.ctx
+            .banks_client
+            .get_account(address)
+            .await
+            .unwrap()
+            .map(|account| account.data)
+    }
+
+    async fn transfer_ix(&mut self, from: &Holder, to: &Holder, amount: u64) -> Instruction {
+        self.transfer_ix_as(from, to, &from.owner(), amount).await
+    }
+
+    // transfer_checked signed by `authority`, the owner or a delegate.
+    async fn transfer_ix_as(
+        &mut self,
+        from: &Holder,
+        to: &Holder,
+        authority: &Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        let mut ix = spl_token_2022::instruction::transfer_checked(
+            &spl_token_2022::id(),
+            &from.token_account,
+            &self.mint,
+            &to.token_account,
+            authority,
+            &[],
+            amount,
+            DECIMALS,
+        )
+        .unwrap();
+        let hook_accounts = support::hook_accounts(
+            &mut self.ctx.banks_client,
+            &from.token_account,
+            &self.mint,
+            &to.token_account,
+            authority,
+            amount,
+        )
+        .await;
+        ix.accounts.extend(hook_accounts);
+        ix
+    }
+
+    async fn transfer(
+        &mut self,
+        from: &Holder,
+        to: &Holder,
+        amount: u64,
+    ) -> Result<(), TransactionError> {
+        let ix = self.transfer_ix(from, to, amount).await;
+        send(&mut self.ctx, &[ix], &[&from.keypair]).await
+    }
+}
+
+#[tokio::test]
+async fn transfer_requires_approved_kyc() {
+    let mut env = setup().await;
+    let sender = env.create_holder(100 * TOKENS, Some(KycLevel::Basic)).await;
+    let outsider = env.create_holder(100 * TOKENS, None).await;
+    let recipient = env.create_holder(0, None).await;
+
+    env.transfer(&sender, &recipient, 10 * TOKENS)
+        .await
+        .unwrap();
+    assert!(env
+        .transfer(&outsider, &recipient, 10 * TOKENS)
+        .await
+        .is_err());
+
+    let ix = revoke_kyc_ix(env.authority(), sender.owner());
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+    let result = env.transfer(&sender, &recipient, 5 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::KycFailed.into());
+}
+
+#[tokio::test]
+async fn expired_kyc_blocks_transfers_until_renewed() {
+    let mut env = setup().await;
+    let sender = env.create_holder(100 * TOKENS, Some(KycLevel::Basic)).await;
+    let recipient = env.create_holder(0, None).await;
+
+    let expires_at = env.kyc(&sender.owner()).await.expires_at;
+    set_time(&mut env.ctx, expires_at).await;
+    let result = env.transfer(&sender, &recipient, 10 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::KycExpired.into());
+
+    let ix = renew_kyc_ix(
+        env.authority(),
+        sender.owner(),
+        KycLevel::Enhanced,
+        expires_at + DAY,
+    );
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+    env.transfer(&sender, &recipient, 10 * TOKENS)
+        .await
+        .unwrap();
+
+    let kyc = env.kyc(&sender.owner()).await;
+    assert_eq!(kyc.level, KycLevel::Enhanced);
+    assert_eq!(kyc.expires_at, expires_at + DAY);
+}
+
+#[tokio::test]
+async fn renew_kyc_rejects_revoked_or_past_expiry() {
+    let mut env = setup().await;
+    let holder = env.create_holder(0, Some(KycLevel::Basic)).await;
+    let authority = env.authority();
+    let now = now(&mut env.ctx).await;
+
+    let ix = renew_kyc_ix(authority, holder.owner(), KycLevel::Basic, now - 1);
+    let result = send(&mut env.ctx, &[ix], &[]).await;
+    assert_custom_error(result, hook::ErrorCode::InvalidExpiry.into());
+
+    let ix = revoke_kyc_ix(authority, holder.owner());
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+    let ix = renew_kyc_ix(authority, holder.owner(), KycLevel::Basic, now + DAY);
+    let result = send(&mut env.ctx, &[ix], &[]).await;
+    assert_custom_error(result, hook::ErrorCode::KycRevoked.into());
+}
+
+#[tokio::test]
+async fn transfer_limits_follow_kyc_tier() {
+    let mut env = setup().await;
+    let authority = env.authority();
+    let basic = env.create_holder(100 * TOKENS, Some(KycLevel::Basic)).await;
+    let institution = env
+        .create_holder(100 * TOKENS, Some(KycLevel::Institutional))
+        .await;
+    let recipient = env.create_holder(0, None).await;
+
+    let ixs = [
+        initialize_mint_config_ix(authority, env.mint, false),
+        set_transfer_limits_ix(
+            authority,
+            env.mint,
+            KycLevel::Basic,
+            50 * TOKENS,
+            10 * TOKENS,
+        ),
+        set_transfer_limits_ix(
+            authority,
+            env.mint,
+            KycLevel::Institutional,
+            1_000 * TOKENS,
+            100 * TOKENS,
+        ),
+    ];
+    send(&mut env.ctx, &ixs, &[]).await.unwrap();
+    env.init_usage(&basic).await;
+    env.init_usage(&institution).await;
+
+    let result = env.transfer(&basic, &recipient, 20 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::TransferLimitExceeded.into());
+    env.transfer(&basic, &recipient, 10 * TOKENS).await.unwrap();
+    env.transfer(&institution, &recipient, 20 * TOKENS)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn senders_without_kyc_get_basic_limits() {
+    let mut env = setup().await;
+    let authority = env.authority();
+    let unverified = env.create_holder(100 * TOKENS, None).await;
+    let recipient = env.create_holder(0, None).await;
+
+    let ixs = [
+        hook_ix(
+            hook::accounts::InitializeMintConfig {
+                mint_config: mint_config_address(&env.mint),
+                mint: env.mint,
+                settings: settings_address(),
+                role_assignment: None,
+                authority,
+                system_program: system_program::ID,
+            },
+            hook::instruction::InitializeMintConfig {
+                authority,
+                policy: MintPolicy {
+                    require_sender_kyc: false,
+                    ..MintPolicy::default()
+                },
+            },
+        ),
+        set_transfer_limits_ix(
+            authority,
+            env.mint,
+            KycLevel::Basic,
+            50 * TOKENS,
+            10 * TOKENS,
+        ),
+    ];
+    send(&mut env.ctx, &ixs, &[]).await.unwrap();
+
+    // No KYC record means no tier of its own, not no limits
+    let result = env.transfer(&unverified, &recipient, 5 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::UsageNotInitialized.into());
+    env.init_usage(&unverified).await;
+    let result = env.transfer(&unverified, &recipient, 20 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::TransferLimitExceeded.into());
+    env.transfer(&unverified, &recipient, 10 * TOKENS)
+        .await
+        .unwrap();
+
+    let data = env
+        .account_data(usage_address(&unverified.owner(), &env.mint))
+        .await
+        .unwrap();
+    let usage = UserUsage::try_deserialize(&mut data.as_slice()).unwrap();
+    assert_eq!(usage.last_24h(), Some(10 * TOKENS));
+}
+
+#[tokio::test]
+async fn transfer_limits_can_be_updated_and_paused() {
+    let mut env = setup().await;
+    let authority = env.authority();
+    let sender = env.create_holder(100 * TOKENS, Some(KycLevel::Basic)).await;
+    let recipient = env.create_holder(0, None).await;
+
+    let ixs = [
+        initialize_mint_config_ix(authority, env.mint, false),
+        set_transfer_limits_ix(
+            authority,
+            env.mint,
+            KycLevel::Basic,
+            50 * TOKENS,
+            10 * TOKENS,
+        ),
+    ];
+    send(&mut env.ctx, &ixs, &[]).await.unwrap();
+    env.init_usage(&sender).await;
+    let result = env.transfer(&sender, &recipient, 20 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::TransferLimitExceeded.into());
+
+    // A second set_transfer_limits cannot overwrite the row
+    let ix = set_transfer_limits_ix(
+        authority,
+        env.mint,
+        KycLevel::Basic,
+        50 * TOKENS,
+        30 * TOKENS,
+    );
+    assert!(send(&mut env.ctx, &[ix], &[]).await.is_err());
+
+    let ix = update_transfer_limits_ix(
+        authority,
+        env.mint,
+        KycLevel::Basic,
+        50 * TOKENS,
+        30 * TOKENS,
+        true,
+    );
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+    env.transfer(&sender, &recipient, 20 * TOKENS)
+        .await
+        .unwrap();
+
+    let ix = update_transfer_limits_ix(
+        authority,
+        env.mint,
+        KycLevel::Basic,
+        50 * TOKENS,
+        5 * TOKENS,
+        false,
+    );
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+    let data = env
+        .account_data(mint_config_address(&env.mint))
+        .await
+        .unwrap();
+    let config = MintConfig::try_deserialize(&mut data.as_slice()).unwrap();
+    let limits = config.limits_for(KycLevel::Basic);
+    assert_eq!(limits.transaction_limit, 5 * TOKENS);
+    assert!(!limits.is_active);
+    env.transfer(&sender, &recipient, 25 * TOKENS)
+        .await
+        .unwrap();
+
+    // Limits managers other than the authority need the role
+    let outsider = Keypair::new();
+    let ix = update_transfer_limits_ix(
+        outsider.pubkey(),
+        env.mint,
+        KycLevel::Basic,
+        TOKENS,
+        TOKENS,
+        true,
+    );
+    let result = send(&mut env.ctx, &[ix], &[&outsider]).await;
+    assert_custom_error(result, hook::ErrorCode::Unauthorized.into());
+}
+
+#[tokio::test]
+async fn meta_list_can_be_rewritten() {
+    let mut env = setup().await;
+    let sender = env.create_holder(100 * TOKENS, Some(KycLevel::Basic)).await;
+    let recipient = env.create_holder(0, None).await;
+
+    let ix = hook_ix(
+        hook::accounts::UpdateExtraAccountMetaList {
+            extra_account_meta_list: extra_account_metas_address(&env.mint),
+            mint: env.mint,
+            settings: settings_address(),
+            role_assignment: None,
+            authority: env.authority(),
+            system_program: system_program::ID,
+        },
+        hook::instruction::UpdateExtraAccountMetaList {},
+    );
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+
+    env.transfer(&sender, &recipient, 10 * TOKENS)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn recipient_kyc_is_enforced_when_required() {
+    let mut env = setup().await;
+    let authority = env.authority();
+    let sender = env.create_holder(100 * TOKENS, Some(KycLevel::Basic)).await;
+    let approved = env.create_holder(0, Some(KycLevel::Basic)).await;
+    let outsider = env.create_holder(0, None).await;
+
+    let ix = initialize_mint_config_ix(authority, env.mint, true);
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+
+    let result = env.transfer(&sender, &outsider, 10 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::RecipientKycFailed.into());
+    env.transfer(&sender, &approved, 10 * TOKENS).await.unwrap();
+
+    let ix = revoke_kyc_ix(authority, approved.owner());
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+    let result = env.transfer(&sender, &approved, 5 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::RecipientKycFailed.into());
+
+    let ix = update_mint_config_ix(
+        authority,
+        env.mint,
+        hook::instruction::SetMintPolicy {
+            policy: MintPolicy::default(),
+        },
+    );
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+    env.transfer(&sender, &outsider, 10 * TOKENS).await.unwrap();
+}
+
+#[tokio::test]
+async fn max_balance_caps_non_exempt_holders() {
+    let mut env = setup().await;
+    let authority = env.authority();
+    let sender = env.create_holder(200 * TOKENS, Some(KycLevel::Basic)).await;
+    let holder = env.create_holder(20 * TOKENS, None).await;
+    let vault = env.create_holder(0, None).await;
+
+    let ixs = [
+        initialize_mint_config_ix(authority, env.mint, false),
+        update_mint_config_ix(
+            authority,
+            env.mint,
+            hook::instruction::SetMintPolicy {
+                policy: MintPolicy {
+                    max_balance: Some(50 * TOKENS),
+                    ..MintPolicy::default()
+                },
+            },
+        ),
+    ];
+    send(&mut env.ctx, &ixs, &[]).await.unwrap();
+
+    // The cap applies to the balance after the transfer lands
+    env.transfer(&sender, &holder, 30 * TOKENS).await.unwrap();
+    let result = env.transfer(&sender, &holder, 1).await;
+    assert_custom_error(result, hook::ErrorCode::MaxBalanceExceeded.into());
+
+    // ...of each token account, so the same owner can fill a second one
+    let second = env
+        .open_account(holder.keypair.insecure_clone(), 0, None)
+        .await;
+    env.transfer(&sender, &second, 50 * TOKENS).await.unwrap();
+    let result = env.transfer(&sender, &second, 1).await;
+    assert_custom_error(result, hook::ErrorCode::MaxBalanceExceeded.into());
+
+    // Pool vaults and treasuries are exempt
+    let result = env.transfer(&sender, &vault, 60 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::MaxBalanceExceeded.into());
+    let ix = add_exempt_account_ix(authority, vault.token_account);
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+    env.transfer(&sender, &vault, 60 * TOKENS).await.unwrap();
+}
+
+#[tokio::test]
+async fn mint_config_authority_governs_its_mint() {
+    let mut env = setup().await;
+    let authority = env.authority();
+    let issuer = Keypair::new();
+    let sender = env.create_holder(100 * TOKENS, Some(KycLevel::Basic)).await;
+    let unverified = env.create_holder(100 * TOKENS, None).await;
+    let recipient = env.create_holder(0, None).await;
+
+    let ixs = [
+        hook_ix(
+            hook::accounts::InitializeMintConfig {
+                mint_config: mint_config_address(&env.mint),
+                mint: env.mint,
+                settings: settings_address(),
+                role_assignment: None,
+                authority,
+                system_program: system_program::ID,
+            },
+            hook::instruction::InitializeMintConfig {
+                authority: issuer.pubkey(),
+                policy: MintPolicy {
+                    require_sender_kyc: false,
+                    require_recipient_kyc: false,
+                    limits_enabled: true,
+                    max_balance: None,
+                    allowlist_only: false,
+                },
+            },
+        ),
+        set_transfer_limits_ix(
+            authority,
+            env.mint,
+            KycLevel::Basic,
+            100 * TOKENS,
+            10 * TOKENS,
+        ),
+    ];
+    send(&mut env.ctx, &ixs, &[]).await.unwrap();
+
+    // Sender KYC is off for this mint, but senders without a record are still
+    // held to the Basic tier
+    let result = env.transfer(&unverified, &recipient, 20 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::TransferLimitExceeded.into());
+    let result = env.transfer(&sender, &recipient, 20 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::TransferLimitExceeded.into());
+
+    let ix = update_mint_config_ix(
+        issuer.pubkey(),
+        env.mint,
+        hook::instruction::SetMintPolicy {
+            policy: MintPolicy {
+                require_sender_kyc: true,
+                require_recipient_kyc: false,
+                limits_enabled: false,
+                max_balance: None,
+                allowlist_only: false,
+            },
+        },
+    );
+    send(&mut env.ctx, &[ix], &[&issuer]).await.unwrap();
+    env.transfer(&sender, &recipient, 20 * TOKENS)
+        .await
+        .unwrap();
+    let result = env.transfer(&unverified, &recipient, 10 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::KycFailed.into());
+
+    let pause = |paused| hook::instruction::SetMintPaused { paused };
+    let ix = update_mint_config_ix(issuer.pubkey(), env.mint, pause(true));
+    send(&mut env.ctx, &[ix], &[&issuer]).await.unwrap();
+    let result = env.transfer(&sender, &recipient, 10 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::MintPaused.into());
+
+    // Only the mint's own authority (or the program authority) manages it
+    let outsider = Keypair::new();
+    let ix = update_mint_config_ix(outsider.pubkey(), env.mint, pause(false));
+    let result = send(&mut env.ctx, &[ix], &[&outsider]).await;
+    assert_custom_error(result, hook::ErrorCode::Unauthorized.into());
+
+    let ix = update_mint_config_ix(
+        issuer.pubkey(),
+        env.mint,
+        hook::instruction::SetMintConfigAuthority {
+            new_authority: outsider.pubkey(),
+        },
+    );
+    send(&mut env.ctx, &[ix], &[&issuer]).await.unwrap();
+    let ix = update_mint_config_ix(issuer.pubkey(), env.mint, pause(false));
+    let result = send(&mut env.ctx, &[ix], &[&issuer]).await;
+    assert_custom_error(result, hook::ErrorCode::Unauthorized.into());
+    let ix = update_mint_config_ix(outsider.pubkey(), env.mint, pause(false));
+    send(&mut env.ctx, &[ix], &[&outsider]).await.unwrap();
+    env.transfer(&sender, &recipient, 10 * TOKENS)
+        .await
+        .unwrap();
+
+    // The mint's limit rows follow the same authority
+    let ix = update_transfer_limits_ix(
+        issuer.pubkey(),
+        env.mint,
+        KycLevel::Basic,
+        50 * TOKENS,
+        5 * TOKENS,
+        true,
+    );
+    let result = send(&mut env.ctx, &[ix], &[&issuer]).await;
+    assert_custom_error(result, hook::ErrorCode::Unauthorized.into());
+    let ix = update_transfer_limits_ix(
+        outsider.pubkey(),
+        env.mint,
+        KycLevel::Basic,
+        50 * TOKENS,
+        5 * TOKENS,
+        true,
+    );
+    send(&mut env.ctx, &[ix], &[&outsider]).await.unwrap();
+    let data = env
+        .account_data(mint_config_address(&env.mint))
+        .await
+        .unwrap();
+    let config = MintConfig::try_deserialize(&mut data.as_slice()).unwrap();
+    assert_eq!(
+        config.limits_for(KycLevel::Basic).transaction_limit,
+        5 * TOKENS
+    );
+}
+
+#[tokio::test]
+async fn jurisdiction_policy_checks_both_owners() {
+    let mut env = setup().await;
+    let authority = env.authority();
+    let german = env.create_holder(100 * TOKENS, Some(KycLevel::Basic)).await;
+    let american = env.create_holder(100 * TOKENS, Some(KycLevel::Basic)).await;
+    let french = env.create_holder(0, Some(KycLevel::Basic)).await;
+    let unverified = env.create_holder(0, None).await;
+
+    let ix = set_kyc_jurisdiction_ix(authority, german.owner(), b"de", false);
+    let result = send(&mut env.ctx, &[ix], &[]).await;
+    assert_custom_error(result, hook::ErrorCode::InvalidCountryCode.into());
+    let ixs = [
+        set_kyc_jurisdiction_ix(authority, german.owner(), b"DE", false),
+        set_kyc_jurisdiction_ix(authority, american.owner(), b"US", false),
+        set_kyc_jurisdiction_ix(authority, french.owner(), b"FR", false),
+        initialize_jurisdiction_policy_ix(
+            authority,
+            env.mint,
+            jurisdiction_rules(JurisdictionMode::Blocklist, &[b"US"], false),
+        ),
+    ];
+    send(&mut env.ctx, &ixs, &[]).await.unwrap();
+    assert_eq!(&env.kyc(&german.owner()).await.country, b"DE");
+
+    let result = env.transfer(&german, &american, 10 * TOKENS).await;
+    assert_custom_error(
+        result,
+        hook::ErrorCode::DestinationJurisdictionBlocked.into(),
+    );
+    let result = env.transfer(&american, &french, 10 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::SourceJurisdictionBlocked.into());
+    env.transfer(&german, &french, 10 * TOKENS).await.unwrap();
+    env.transfer(&german, &unverified, 10 * TOKENS)
+        .await
+        .unwrap();
+
+    // An allowlist also shuts out wallets of unknown residence
+    let ix = update_jurisdiction_policy_ix(
+        authority,
+        env.mint,
+        jurisdiction_rules(JurisdictionMode::Allowlist, &[b"DE", b"FR"], false),
+    );
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+    let result = env.transfer(&german, &unverified, 5 * TOKENS).await;
+    assert_custom_error(
+        result,
+        hook::ErrorCode::DestinationJurisdictionBlocked.into(),
+    );
+    env.transfer(&german, &french, 5 * TOKENS).await.unwrap();
+
+    let ix = update_jurisdiction_policy_ix(
+        authority,
+        env.mint,
+        jurisdiction_rules(JurisdictionMode::Allowlist, &[b"DE", b"FR"], true),
+    );
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+    let result = env.transfer(&german, &french, 4 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::SourceJurisdictionBlocked.into());
+    let ix = set_kyc_jurisdiction_ix(authority, german.owner(), b"DE", true);
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+    let result = env.transfer(&german, &french, 4 * TOKENS).await;
+    assert_custom_error(
+        result,
+        hook::ErrorCode::DestinationJurisdictionBlocked.into(),
+    );
+    let ix = set_kyc_jurisdiction_ix(authority, french.owner(), b"FR", true);
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+    env.transfer(&german, &french, 4 * TOKENS).await.unwrap();
+}
+
+#[tokio::test]
+async fn delegates_cannot_lend_their_jurisdiction() {
+    let mut env = setup().await;
+    let authority = env.authority();
+    let german = env.create_holder(0, Some(KycLevel::Basic)).await;
+    let american = env.create_holder(100 * TOKENS, Some(KycLevel::Basic)).await;
+    let french = env.create_holder(0, Some(KycLevel::Basic)).await;
+
+    let ixs = [
+        set_kyc_jurisdiction_ix(authority, german.owner(), b"DE", false),
+        set_kyc_jurisdiction_ix(authority, american.owner(), b"US", false),
+        set_kyc_jurisdiction_ix(authority, french.owner(), b"FR", false),
+        initialize_jurisdiction_policy_ix(
+            authority,
+            env.mint,
+            jurisdiction_rules(JurisdictionMode::Blocklist, &[b"US"], false),
+        ),
+        spl_token_2022::instruction::approve(
+            &spl_token_2022::id(),
+            &american.token_account,
+            &german.owner(),
+            &american.owner(),
+            &[],
+            50 * TOKENS,
+        )
+        .unwrap(),
+    ];
+    send(&mut env.ctx, &ixs, &[&american.keypair])
+        .await
+        .unwrap();
+
+    // The German delegate spending American-held tokens is judged by the holder
+    let ix = env
+        .transfer_ix_as(&american, &french, &german.owner(), 10 * TOKENS)
+        .await;
+    let result = send(&mut env.ctx, &[ix], &[&german.keypair]).await;
+    assert_custom_error(result, hook::ErrorCode::SourceJurisdictionBlocked.into());
+
+    let ix = update_jurisdiction_policy_ix(
+        authority,
+        env.mint,
+        jurisdiction_rules(JurisdictionMode::Blocklist, &[b"CN"], false),
+    );
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+    let ix = env
+        .transfer_ix_as(&american, &french, &german.owner(), 10 * TOKENS)
+        .await;
+    send(&mut env.ctx, &[ix], &[&german.keypair]).await.unwrap();
+}
+
+#[tokio::test]
+async fn lockups_hold_back_unvested_tokens() {
+    let mut env = setup().await;
+    let authority = env.authority();
+    let investor = env.create_holder(100 * TOKENS, Some(KycLevel::Basic)).await;
+    let founder = env.create_holder(100 * TOKENS, Some(KycLevel::Basic)).await;
+    let recipient = env.create_holder(0, Some(KycLevel::Basic)).await;
+    let start = now(&mut env.ctx).await;
+    let schedule = LockupSchedule {
+        amount: 60 * TOKENS,
+        start_at: start,
+        cliff_at: start + 10 * DAY,
+        end_at: start + 40 * DAY,
+    };
+
+    let ix = create_lockup_ix(
+        authority,
+        env.mint,
+        investor.token_account,
+        LockupSchedule {
+            cliff_at: start + 50 * DAY,
+            ..schedule
+        },
+    );
+    let result = send(&mut env.ctx, &[ix], &[]).await;
+    assert_custom_error(result, hook::ErrorCode::InvalidLockupSchedule.into());
+    let ixs = [
+        create_lockup_ix(authority, env.mint, investor.token_account, schedule),
+        create_lockup_ix(authority, env.mint, founder.token_account, schedule),
+    ];
+    send(&mut env.ctx, &ixs, &[]).await.unwrap();
+
+    // Before the cliff only the unlocked 40 can move
+    let result = env.transfer(&investor, &recipient, 41 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::LockupViolation.into());
+    env.transfer(&investor, &recipient, 40 * TOKENS)
+        .await
+        .unwrap();
+
+    // Halfway through, 30 of the 60 have vested
+    set_time(&mut env.ctx, start + 20 * DAY).await;
+    let result = env.transfer(&investor, &recipient, 31 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::LockupViolation.into());
+    env.transfer(&investor, &recipient, 30 * TOKENS)
+        .await
+        .unwrap();
+
+    // Owners can only clear their own lockup once it has fully vested...
+    let ix = release_lockup_ix(investor.owner(), investor.token_account);
+    let result = send(&mut env.ctx, &[ix], &[&investor.keypair]).await;
+    assert_custom_error(result, hook::ErrorCode::LockupStillActive.into());
+    let ix = release_lockup_ix(investor.owner(), founder.token_account);
+    let result = send(&mut env.ctx, &[ix], &[&investor.keypair]).await;
+    assert_custom_error(result, hook::ErrorCode::Unauthorized.into());
+
+    // ...while the authority can lift one early
+    let ix = release_lockup_ix(authority, founder.token_account);
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+    env.transfer(&founder, &recipient, 100 * TOKENS)
+        .await
+        .unwrap();
+
+    set_time(&mut env.ctx, start + 40 * DAY).await;
+    env.transfer(&investor, &recipient, 30 * TOKENS)
+        .await
+        .unwrap();
+    let ix = release_lockup_ix(investor.owner(), investor.token_account);
+    send(&mut env.ctx, &[ix], &[&investor.keypair])
+        .await
+        .unwrap();
+    assert!(env
+        .account_data(lockup_address(&investor.token_account))
+        .await
+        .is_none());
+}
+
+#[tokio::test]
+async fn lockups_only_hold_their_own_token_account() {
+    let mut env = setup().await;
+    let authority = env.authority();
+    let investor = env.create_holder(100 * TOKENS, Some(KycLevel::Basic)).await;
+    let second = env
+        .open_account(investor.keypair.insecure_clone(), 100 * TOKENS, None)
+        .await;
+    let recipient = env.create_holder(0, Some(KycLevel::Basic)).await;
+    let start = now(&mut env.ctx).await;
+    let schedule = LockupSchedule {
+        amount: 60 * TOKENS,
+        start_at: start,
+        cliff_at: start + 10 * DAY,
+        end_at: start + 40 * DAY,
+    };
+    let ix = create_lockup_ix(authority, env.mint, investor.token_account, schedule);
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+
+    // The owner's other account for the same mint is not locked at all
+    env.transfer(&second, &recipient, 100 * TOKENS)
+        .await
+        .unwrap();
+    let result = env.transfer(&investor, &recipient, 41 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::LockupViolation.into());
+}
+
+#[tokio::test]
+async fn lockups_follow_the_account_to_a_new_owner() {
+    let mut env = setup().await;
+    let authority = env.authority();
+    let investor = env.create_holder(100 * TOKENS, Some(KycLevel::Basic)).await;
+    let recipient = env.create_holder(0, Some(KycLevel::Basic)).await;
+    let start = now(&mut env.ctx).await;
+    let schedule = LockupSchedule {
+        amount: 60 * TOKENS,
+        start_at: start,
+        cliff_at: start + 10 * DAY,
+        end_at: start + 40 * DAY,
+    };
+    let ix = create_lockup_ix(authority, env.mint, investor.token_account, schedule);
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+
+    // Handing the account to another wallet does not shed the lockup
+    let buyer = Holder {
+        keypair: Keypair::new(),
+        token_account: investor.token_account,
+    };
+    let ixs = [
+        spl_token_2022::instruction::set_authority(
+            &spl_token_2022::id(),
+            &investor.token_account,
+            Some(&buyer.owner()),
+            AuthorityType::AccountOwner,
+            &investor.owner(),
+            &[],
+        )
+        .unwrap(),
+        create_kyc_ix(authority, buyer.owner(), KycLevel::Basic, start + 365 * DAY),
+    ];
+    send(&mut env.ctx, &ixs, &[&investor.keypair])
+        .await
+        .unwrap();
+    let result = env.transfer(&buyer, &recipient, 41 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::LockupViolation.into());
+    env.transfer(&buyer, &recipient, 40 * TOKENS).await.unwrap();
+
+    // Once vested, only the account's current owner may close the lockup
+    set_time(&mut env.ctx, start + 40 * DAY).await;
+    let ix = release_lockup_ix(investor.owner(), investor.token_account);
+    let result = send(&mut env.ctx, &[ix], &[&investor.keypair]).await;
+    assert_custom_error(result, hook::ErrorCode::Unauthorized.into());
+    let ix = release_lockup_ix(buyer.owner(), investor.token_account);
+    send(&mut env.ctx, &[ix], &[&buyer.keypair]).await.unwrap();
+}
+
+#[tokio::test]
+async fn allowlist_mode_only_moves_between_listed_owners() {
+    let mut env = setup().await;
+    let authority = env.authority();
+    let sender = env.create_holder(100 * TOKENS, Some(KycLevel::Basic)).await;
+    let listed = env.create_holder(0, None).await;
+    let verified = env.create_holder(0, Some(KycLevel::Institutional)).await;
+    let vault = env.create_holder(0, None).await;
+
+    let ixs = [
+        initialize_mint_config_ix(authority, env.mint, false),
+        update_mint_config_ix(
+            authority,
+            env.mint,
+            hook::instruction::SetMintPolicy {
+                policy: MintPolicy {
+                    allowlist_only: true,
+                    ..MintPolicy::default()
+                },
+            },
+        ),
+        add_exempt_account_ix(authority, vault.token_account),
+    ];
+    send(&mut env.ctx, &ixs, &[]).await.unwrap();
+
+    let result = env.transfer(&sender, &listed, 10 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::SourceNotAllowlisted.into());
+
+    let ixs = [
+        add_to_allowlist_ix(authority, env.mint, sender.owner()),
+        add_to_allowlist_ix(authority, env.mint, listed.owner()),
+    ];
+    send(&mut env.ctx, &ixs, &[]).await.unwrap();
+    env.transfer(&sender, &listed, 10 * TOKENS).await.unwrap();
+
+    // Neither KYC nor an exemption stands in for a listing
+    let result = env.transfer(&sender, &verified, 10 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::DestinationNotAllowlisted.into());
+    let result = env.transfer(&sender, &vault, 10 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::DestinationNotAllowlisted.into());
+
+    // Only managers maintain the list
+    let outsider = Keypair::new();
+    let ixs = [
+        system_instruction::transfer(&authority, &outsider.pubkey(), 1_000_000_000),
+        add_to_allowlist_ix(outsider.pubkey(), env.mint, outsider.pubkey()),
+    ];
+    let result = send(&mut env.ctx, &ixs, &[&outsider]).await;
+    assert_custom_error(result, hook::ErrorCode::Unauthorized.into());
+
+    let ix = remove_from_allowlist_ix(authority, env.mint, sender.owner());
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+    let result = env.transfer(&sender, &listed, 5 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::SourceNotAllowlisted.into());
+}
+
+#[tokio::test]
+async fn exempt_accounts_skip_kyc() {
+    let mut env = setup().await;
+    let authority = env.authority();
+    let vault = env.create_holder(100 * TOKENS, None).await;
+    let treasury = env.create_holder(100 * TOKENS, None).await;
+    let recipient = env.create_holder(0, None).await;
+
+    let result = env.transfer(&vault, &recipient, 10 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::KycFailed.into());
+
+    // Exempt by token account or by owner
+    let ixs = [
+        add_exempt_account_ix(authority, vault.token_account),
+        add_exempt_account_ix(authority, treasury.owner()),
+    ];
+    send(&mut env.ctx, &ixs, &[]).await.unwrap();
+    env.transfer(&vault, &recipient, 10 * TOKENS).await.unwrap();
+    env.transfer(&treasury, &recipient, 10 * TOKENS)
+        .await
+        .unwrap();
+
+    // Exempt destinations also satisfy a recipient KYC requirement
+    let ix = initialize_mint_config_ix(authority, env.mint, true);
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+    env.transfer(&vault, &treasury, 5 * TOKENS).await.unwrap();
+    let result = env.transfer(&vault, &recipient, 5 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::RecipientKycFailed.into());
+
+    let ix = remove_exempt_account_ix(authority, vault.token_account);
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+    let result = env.transfer(&vault, &treasury, 10 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::KycFailed.into());
+}
+
+#[tokio::test]
+async fn denylisted_wallets_cannot_send_or_receive() {
+    let mut env = setup().await;
+    let authority = env.authority();
+    let sender = env.create_holder(100 * TOKENS, Some(KycLevel::Basic)).await;
+    let sanctioned = env.create_holder(100 * TOKENS, Some(KycLevel::Basic)).await;
+
+    // Exemption does not override the denylist
+    let ixs = [
+        add_to_denylist_ix(authority, sanctioned.owner(), 7),
+        add_exempt_account_ix(authority, sanctioned.token_account),
+    ];
+    send(&mut env.ctx, &ixs, &[]).await.unwrap();
+
+    let result = env.transfer(&sanctioned, &sender, 10 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::SourceDenylisted.into());
+    let result = env.transfer(&sender, &sanctioned, 10 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::DestinationDenylisted.into());
+
+    let ix = remove_from_denylist_ix(authority, sanctioned.owner());
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+    env.transfer(&sanctioned, &sender, 10 * TOKENS)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn kyc_operator_role_is_scoped() {
+    let mut env = setup().await;
+    let authority = env.authority();
+    let operator = Keypair::new();
+    let outsider = Keypair::new();
+    let user = Pubkey::new_unique();
+    let expires_at = now(&mut env.ctx).await + DAY;
+    send(
+        &mut env.ctx,
+        &[
+            system_instruction::transfer(&authority, &operator.pubkey(), 1_000_000_000),
+            system_instruction::transfer(&authority, &outsider.pubkey(), 1_000_000_000),
+            initialize_mint_config_ix(authority, env.mint, false),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // Without the role the operator is just another signer
+    let ix = create_kyc_ix(operator.pubkey(), user, KycLevel::Basic, expires_at);
+    let result = send(&mut env.ctx, &[ix], &[&operator]).await;
+    assert_custom_error(result, hook::ErrorCode::Unauthorized.into());
+
+    let ix = grant_role_ix(authority, operator.pubkey(), Role::KycOperator);
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+
+    let ix = with_role(
+        create_kyc_ix(operator.pubkey(), user, KycLevel::Basic, expires_at),
+        operator.pubkey(),
+        Role::KycOperator,
+    );
+    send(&mut env.ctx, &[ix], &[&operator]).await.unwrap();
+    assert_eq!(env.kyc(&user).await.level, KycLevel::Basic);
+
+    // An assignment only counts for its own member
+    let ix = with_role(
+        create_kyc_ix(
+            outsider.pubkey(),
+            Pubkey::new_unique(),
+            KycLevel::Basic,
+            expires_at,
+        ),
+        operator.pubkey(),
+        Role::KycOperator,
+    );
+    let result = send(&mut env.ctx, &[ix], &[&outsider]).await;
+    assert_custom_error(result, hook::ErrorCode::Unauthorized.into());
+
+    // ...and the operator cannot use it for limits or to hand out roles
+    let ix = with_role(
+        set_transfer_limits_ix(operator.pubkey(), env.mint, KycLevel::Basic, 1, 1),
+        operator.pubkey(),
+        Role::KycOperator,
+    );
+    let result = send(&mut env.ctx, &[ix], &[&operator]).await;
+    assert_custom_error(result, hook::ErrorCode::Unauthorized.into());
+
+    let ix = grant_role_ix(operator.pubkey(), operator.pubkey(), Role::LimitsManager);
+    let result = send(&mut env.ctx, &[ix], &[&operator]).await;
+    assert_custom_error(result, hook::ErrorCode::Unauthorized.into());
+}
+
+#[tokio::test]
+async fn authority_transfer_needs_acceptance() {
+    let mut env = setup().await;
+    let authority = env.authority();
+    let typo = Keypair::new();
+    let successor = Keypair::new();
+
+    let ix = propose_authority_ix(authority, typo.pubkey());
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+    assert_eq!(env.settings().await.authority, authority);
+
+    // A proposal can be cancelled or replaced before it is accepted
+    let ix = hook_ix(
+        hook::accounts::CancelAuthorityTransfer {
+            settings: settings_address(),
+            authority,
+        },
+        hook::instruction::CancelAuthorityTransfer {},
+    );
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+    let result = send(
+        &mut env.ctx,
+        &[accept_authority_ix(typo.pubkey())],
+        &[&typo],
+    )
+    .await;
+    assert_custom_error(result, hook::ErrorCode::Unauthorized.into());
+
+    let ix = propose_authority_ix(authority, successor.pubkey());
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+    let ix = accept_authority_ix(successor.pubkey());
+    send(&mut env.ctx, &[ix], &[&successor]).await.unwrap();
+
+    let settings = env.settings().await;
+    assert_eq!(settings.authority, successor.pubkey());
+    assert_eq!(settings.pending_authority, None);
+}
+
+#[tokio::test]
+async fn pauser_can_halt_all_transfers() {
+    let mut env = setup().await;
+    let authority = env.authority();
+    let pauser = Keypair::new();
+    let sender = env.create_holder(100 * TOKENS, Some(KycLevel::Basic)).await;
+    let vault = env.create_holder(100 * TOKENS, None).await;
+    let recipient = env.create_holder(0, None).await;
+
+    let ixs = [
+        grant_role_ix(authority, pauser.pubkey(), Role::Pauser),
+        add_exempt_account_ix(authority, vault.token_account),
+    ];
+    send(&mut env.ctx, &ixs, &[]).await.unwrap();
+    let ix = with_role(
+        set_hook_active_ix(pauser.pubkey(), false),
+        pauser.pubkey(),
+        Role::Pauser,
+    );
+    send(&mut env.ctx, &[ix], &[&pauser]).await.unwrap();
+    assert!(!env.settings().await.is_active);
+
+    // Exempt accounts are paused too
+    let result = env.transfer(&sender, &recipient, 10 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::HookPaused.into());
+    let result = env.transfer(&vault, &recipient, 10 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::HookPaused.into());
+
+    let ix = set_hook_active_ix(authority, true);
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+    env.transfer(&sender, &recipient, 10 * TOKENS)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn kyc_batches_create_and_revoke_many_users() {
+    let mut env = setup().await;
+    let authority = env.authority();
+    let expires_at = now(&mut env.ctx).await + DAY;
+    let existing = Pubkey::new_unique();
+    let users: Vec<Pubkey> = (0..8).map(|_| Pubkey::new_unique()).collect();
+
+    let ix = create_kyc_ix(authority, existing, KycLevel::Institutional, expires_at);
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+
+    // Existing records are left alone
+    let mut ix = hook_ix(
+        hook::accounts::CreateKycBatch {
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+            system_program: system_program::ID,
+        },
+        hook::instruction::CreateKycBatch {
+            level: KycLevel::Enhanced,
+            expires_at,
+        },
+    );
+    for user in users.iter().chain([&existing]) {
+        ix.accounts.push(AccountMeta::new_readonly(*user, false));
+        ix.accounts.push(AccountMeta::new(kyc_address(user), false));
+        ix.accounts.push(AccountMeta::new_readonly(
+            kyc_tombstone_address(user),
+            false,
+        ));
+    }
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+
+    for user in &users {
+        let kyc = env.kyc(user).await;
+        assert_eq!(
+            (kyc.user, kyc.status, kyc.level),
+            (*user, 1, KycLevel::Enhanced)
+        );
+    }
+    assert_eq!(env.kyc(&existing).await.level, KycLevel::Institutional);
+
+    let mut ix = hook_ix(
+        hook::accounts::RevokeKycBatch {
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+        },
+        hook::instruction::RevokeKycBatch {},
+    );
+    for user in &users[..4] {
+        ix.accounts.push(AccountMeta::new(kyc_address(user), false));
+    }
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+
+    for (i, user) in users.iter().enumerate() {
+        assert_eq!(env.kyc(user).await.status, u8::from(i >= 4));
+    }
+
+    // A PDA that does not belong to the paired user is rejected
+    let mut ix = hook_ix(
+        hook::accounts::CreateKycBatch {
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+            system_program: system_program::ID,
+        },
+        hook::instruction::CreateKycBatch {
+            level: KycLevel::Basic,
+            expires_at,
+        },
+    );
+    ix.accounts
+        .push(AccountMeta::new_readonly(Pubkey::new_unique(), false));
+    ix.accounts
+        .push(AccountMeta::new(kyc_address(&Pubkey::new_unique()), false));
+    ix.accounts
+        .push(AccountMeta::new_readonly(Pubkey::new_unique(), false));
+    let result = send(&mut env.ctx, &[ix], &[]).await;
+    assert_custom_error(result, hook::ErrorCode::InvalidKycAccount.into());
+}
+
+#[tokio::test]
+async fn close_kyc_only_for_revoked_records() {
+    let mut env = setup().await;
+    let authority = env.authority();
+    let holder = env.create_holder(0, Some(KycLevel::Basic)).await;
+    let recipient = Pubkey::new_unique();
+    let close_ix = hook_ix(
+        hook::accounts::CloseKyc {
+            kyc: kyc_address(&holder.owner()),
+            kyc_tombstone: kyc_tombstone_address(&holder.owner()),
+            recipient,
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+            system_program: system_program::ID,
+        },
+        hook::instruction::CloseKyc {},
+    );
+
+    let result = send(&mut env.ctx, std::slice::from_ref(&close_ix), &[]).await;
+    assert_custom_error(result, hook::ErrorCode::KycNotRevoked.into());
+
+    let rent = env.lamports(kyc_address(&holder.owner())).await;
+    let ix = revoke_kyc_ix(authority, holder.owner());
+    send(&mut env.ctx, &[ix, close_ix], &[]).await.unwrap();
+
+    assert!(env
+        .account_data(kyc_address(&holder.owner()))
+        .await
+        .is_none());
+    assert_eq!(env.lamports(recipient).await, rent);
+
+    // The tombstone keeps the history and stops a fresh record replacing it
+    let data = env
+        .account_data(kyc_tombstone_address(&holder.owner()))
+        .await
+        .unwrap();
+    let tombstone = KycTombstone::try_deserialize(&mut data.as_slice()).unwrap();
+    assert_eq!(tombstone.user, holder.owner());
+    assert_eq!(tombstone.revocation_count, 1);
+    let expires_at = now(&mut env.ctx).await + DAY;
+    let ix = create_kyc_ix(authority, holder.owner(), KycLevel::Basic, expires_at);
+    let result = send(&mut env.ctx, &[ix], &[]).await;
+    assert_custom_error(result, hook::ErrorCode::KycRecordClosed.into());
+
+    let mut ix = hook_ix(
+        hook::accounts::CreateKycBatch {
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+            system_program: system_program::ID,
+        },
+        hook::instruction::CreateKycBatch {
+            level: KycLevel::Basic,
+            expires_at,
+        },
+    );
+    ix.accounts
+        .push(AccountMeta::new_readonly(holder.owner(), false));
+    ix.accounts
+        .push(AccountMeta::new(kyc_address(&holder.owner()), false));
+    ix.accounts.push(AccountMeta::new_readonly(
+        kyc_tombstone_address(&holder.owner()),
+        false,
+    ));
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+    assert!(env
+        .account_data(kyc_address(&holder.owner()))
+        .await
+        .is_none());
+}
+
+#[tokio::test]
+async fn daily_usage_accumulates_across_transfers() {
+    let mut env = setup().await;
+    let authority = env.authority();
+    let holder = env.create_holder(100 * TOKENS, Some(KycLevel::Basic)).await;
+    let recipient = env.create_holder(0, None).await;
+    send(
+        &mut env.ctx,
+        &[
+            system_instruction::transfer(&authority, &holder.owner(), 1_000_000_000),
+            initialize_mint_config_ix(authority, env.mint, false),
+            set_transfer_limits_ix(authority, env.mint, KycLevel::Basic, 3 * TOKENS, 2 * TOKENS),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // Usage has to be initialized before it can be checked or transferred against
+    let ix = check_transfer_limits_ix(holder.owner(), env.mint, TOKENS);
+    assert!(send(&mut env.ctx, &[ix], &[&holder.keypair]).await.is_err());
+    let result = env.transfer(&holder, &recipient, TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::UsageNotInitialized.into());
+
+    let ix = init_user_usage_ix(holder.owner(), env.mint);
+    send(&mut env.ctx, &[ix], &[&holder.keypair]).await.unwrap();
+    env.transfer(&holder, &recipient, 2 * TOKENS).await.unwrap();
+
+    // Checks are a dry run and do not count against the window
+    let ix = check_transfer_limits_ix(holder.owner(), env.mint, TOKENS);
+    send(&mut env.ctx, &[ix], &[&holder.keypair]).await.unwrap();
+    env.transfer(&holder, &recipient, TOKENS).await.unwrap();
+
+    let data = env
+        .account_data(usage_address(&holder.owner(), &env.mint))
+        .await
+        .unwrap();
+    let usage = UserUsage::try_deserialize(&mut data.as_slice()).unwrap();
+    assert_eq!(usage.user, holder.owner());
+    assert_eq!(usage.mint, env.mint);
+    assert_eq!(usage.last_24h(), Some(3 * TOKENS));
+
+    let ix = check_transfer_limits_ix(holder.owner(), env.mint, 1);
+    let result = send(&mut env.ctx, &[ix], &[&holder.keypair]).await;
+    assert_custom_error(result, hook::ErrorCode::DailyLimitExceeded.into());
+    let result = env.transfer(&holder, &recipient, 1).await;
+    assert_custom_error(result, hook::ErrorCode::DailyLimitExceeded.into());
+
+    // Uncapped limits still reject a running total that would wrap
+    let ix = update_transfer_limits_ix(
+        authority,
+        env.mint,
+        KycLevel::Basic,
+        u64::MAX,
+        u64::MAX,
+        true,
+    );
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+    let ix = check_transfer_limits_ix(holder.owner(), env.mint, u64::MAX);
+    let result = send(&mut env.ctx, &[ix], &[&holder.keypair]).await;
+    assert_custom_error(result, hook::ErrorCode::MathOverflow.into());
+}
+
+#[tokio::test]
+async fn usage_limits_use_rolling_windows() {
+    let mut env = setup().await;
+    let authority = env.authority();
+    let holder = env.create_holder(100 * TOKENS, Some(KycLevel::Basic)).await;
+    let recipient = env.create_holder(0, None).await;
+    let limits = hook_ix(
+        hook::accounts::SetTransferLimits {
+            mint_config: mint_config_address(&env.mint),
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+        },
+        hook::instruction::SetTransferLimits {
+            level: KycLevel::Basic,
+            limits: TransferLimits {
+                daily_limit: 10 * TOKENS,
+                transaction_limit: 10 * TOKENS,
+                weekly_limit: Some(25 * TOKENS),
+                monthly_limit: Some(35 * TOKENS),
+                max_daily_transfers: None,
+                cooldown_seconds: None,
+            },
+        },
+    );
+    send(
+        &mut env.ctx,
+        &[
+            system_instruction::transfer(&authority, &holder.owner(), 1_000_000_000),
+            initialize_mint_config_ix(authority, env.mint, false),
+            limits,
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // Half an hour before midnight UTC
+    let start = (now(&mut env.ctx).await / DAY + 1) * DAY - DAY / 48;
+    set_time(&mut env.ctx, start).await;
+    let ix = init_user_usage_ix(holder.owner(), env.mint);
+    send(&mut env.ctx, &[ix], &[&holder.keypair]).await.unwrap();
+    env.transfer(&holder, &recipient, 10 * TOKENS)
+        .await
+        .unwrap();
+
+    // A new calendar day does not reset the 24-hour window
+    set_time(&mut env.ctx, start + DAY / 24).await;
+    let result = env.transfer(&holder, &recipient, TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::DailyLimitExceeded.into());
+
+    set_time(&mut env.ctx, start + DAY).await;
+    env.transfer(&holder, &recipient, 10 * TOKENS)
+        .await
+        .unwrap();
+
+    // 20 tokens already moved this week
+    set_time(&mut env.ctx, start + 2 * DAY).await;
+    let result = env.transfer(&holder, &recipient, 6 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::WeeklyLimitExceeded.into());
+
+    // Day one leaves the 7-day window but still counts towards the 30-day cap
+    set_time(&mut env.ctx, start + 7 * DAY).await;
+    env.transfer(&holder, &recipient, 10 * TOKENS)
+        .await
+        .unwrap();
+
+    set_time(&mut env.ctx, start + 8 * DAY).await;
+    let result = env.transfer(&holder, &recipient, 6 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::MonthlyLimitExceeded.into());
+
+    // ...until it ages out of that as well
+    set_time(&mut env.ctx, start + 30 * DAY).await;
+    env.transfer(&holder, &recipient, 10 * TOKENS)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn velocity_limits_are_enforced_by_the_hook() {
+    let mut env = setup().await;
+    let authority = env.authority();
+    let sender = env.create_holder(100 * TOKENS, Some(KycLevel::Basic)).await;
+    let recipient = env.create_holder(0, None).await;
+    let limits = hook_ix(
+        hook::accounts::SetTransferLimits {
+            mint_config: mint_config_address(&env.mint),
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+        },
+        hook::instruction::SetTransferLimits {
+            level: KycLevel::Basic,
+            limits: TransferLimits {
+                daily_limit: 100 * TOKENS,
+                transaction_limit: 100 * TOKENS,
+                weekly_limit: None,
+                monthly_limit: None,
+                max_daily_transfers: Some(2),
+                cooldown_seconds: Some(60),
+            },
+        },
+    );
+    send(
+        &mut env.ctx,
+        &[
+            system_instruction::transfer(&authority, &sender.owner(), 1_000_000_000),
+            initialize_mint_config_ix(authority, env.mint, false),
+            limits,
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // Velocity limits need somewhere to count transfers
+    let result = env.transfer(&sender, &recipient, TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::UsageNotInitialized.into());
+    let ix = init_user_usage_ix(sender.owner(), env.mint);
+    send(&mut env.ctx, &[ix], &[&sender.keypair]).await.unwrap();
+
+    let start = now(&mut env.ctx).await;
+    env.transfer(&sender, &recipient, TOKENS).await.unwrap();
+    let result = env.transfer(&sender, &recipient, 2 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::TransferCooldownActive.into());
+
+    set_time(&mut env.ctx, start + 60).await;
+    env.transfer(&sender, &recipient, 3 * TOKENS).await.unwrap();
+
+    set_time(&mut env.ctx, start + 120).await;
+    let result = env.transfer(&sender, &recipient, 4 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::TransferCountExceeded.into());
+
+    let data = env
+        .account_data(usage_address(&sender.owner(), &env.mint))
+        .await
+        .unwrap();
+    let usage = UserUsage::try_deserialize(&mut data.as_slice()).unwrap();
+    assert_eq!(usage.transfers_last_24h(), 2);
+    assert_eq!(usage.last_transaction, start + 60);
+
+    set_time(&mut env.ctx, start + DAY).await;
+    env.transfer(&sender, &recipient, 5 * TOKENS).await.unwrap();
+}
+
+#[tokio::test]
+async fn rolling_caps_apply_to_senders_without_kyc() {
+    let mut env = setup().await;
+    let authority = env.authority();
+    let unverified = env.create_holder(100 * TOKENS, None).await;
+    let recipient = env.create_holder(0, None).await;
+    let ixs = [
+        hook_ix(
+            hook::accounts::InitializeMintConfig {
+                mint_config: mint_config_address(&env.mint),
+                mint: env.mint,
+                settings: settings_address(),
+                role_assignment: None,
+                authority,
+                system_program: system_program::ID,
+            },
+            hook::instruction::InitializeMintConfig {
+                authority,
+                policy: MintPolicy {
+                    require_sender_kyc: false,
+                    ..MintPolicy::default()
+                },
+            },
+        ),
+        hook_ix(
+            hook::accounts::SetTransferLimits {
+                mint_config: mint_config_address(&env.mint),
+                settings: settings_address(),
+                role_assignment: None,
+                authority,
+            },
+            hook::instruction::SetTransferLimits {
+                level: KycLevel::Basic,
+                limits: TransferLimits {
+                    daily_limit: 20 * TOKENS,
+                    transaction_limit: 20 * TOKENS,
+                    weekly_limit: None,
+                    monthly_limit: None,
+                    max_daily_transfers: Some(2),
+                    cooldown_seconds: None,
+                },
+            },
+        ),
+    ];
+    send(&mut env.ctx, &ixs, &[]).await.unwrap();
+    env.init_usage(&unverified).await;
+
+    let start = now(&mut env.ctx).await;
+    env.transfer(&unverified, &recipient, 10 * TOKENS)
+        .await
+        .unwrap();
+    let result = env.transfer(&unverified, &recipient, 15 * TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::DailyLimitExceeded.into());
+    env.transfer(&unverified, &recipient, 5 * TOKENS)
+        .await
+        .unwrap();
+    let result = env.transfer(&unverified, &recipient, TOKENS).await;
+    assert_custom_error(result, hook::ErrorCode::TransferCountExceeded.into());
+
+    set_time(&mut env.ctx, start + DAY).await;
+    env.transfer(&unverified, &recipient, 20 * TOKENS)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn close_user_usage_after_idle_period() {
+    let mut env = setup().await;
+    let authority = env.authority();
+    let holder = env.create_holder(0, Some(KycLevel::Basic)).await;
+    let recipient = Pubkey::new_unique();
+    send(
+        &mut env.ctx,
+        &[
+            system_instruction::transfer(&authority, &holder.owner(), 1_000_000_000),
+            initialize_mint_config_ix(authority, env.mint, false),
+            set_transfer_limits_ix(authority, env.mint, KycLevel::Basic, TOKENS, TOKENS),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let ixs = [
+        init_user_usage_ix(holder.owner(), env.mint),
+        check_transfer_limits_ix(holder.owner(), env.mint, TOKENS),
+    ];
+    send(&mut env.ctx, &ixs, &[&holder.keypair]).await.unwrap();
+
+    let close_ix = hook_ix(
+        hook::accounts::CloseUserUsage {
+            user_usage: usage_address(&holder.owner(), &env.mint),
+            mint: env.mint,
+            recipient,
+            user: holder.owner(),
+        },
+        hook::instruction::CloseUserUsage {},
+    );
+    env.ctx.get_new_latest_blockhash().await.unwrap();
+    let result = send(
+        &mut env.ctx,
+        std::slice::from_ref(&close_ix),
+        &[&holder.keypair],
+    )
+    .await;
+    assert_custom_error(result, hook::ErrorCode::UsageStillActive.into());
+
+    let data = env
+        .account_data(usage_address(&holder.owner(), &env.mint))
+        .await
+        .unwrap();
+    let usage = UserUsage::try_deserialize(&mut data.as_slice()).unwrap();
+    // The retry is byte-identical to the rejected close, so wait for a newer
+    // blockhash to keep the bank from replaying the cached failure.
+    env.ctx.get_new_latest_blockhash().await.unwrap();
+    set_time(&mut env.ctx, (usage.current_day + 30) * DAY).await;
+    send(&mut env.ctx, &[close_ix], &[&holder.keypair])
+        .await
+        .unwrap();
+    assert!(env
+        .account_data(usage_address(&holder.owner(), &env.mint))
+        .await
+        .is_none());
+    assert!(env.lamports(recipient).await > 0);
+}
+
+#[tokio::test]
+async fn revoked_kyc_can_be_reinstated_with_history() {
+    let mut env = setup().await;
+    let authority = env.authority();
+    let sender = env
+        .create_holder(100 * TOKENS, Some(KycLevel::Enhanced))
+        .await;
+    let recipient = env.create_holder(0, None).await;
+    let expires_at = now(&mut env.ctx).await + DAY;
+
+    let ix = reinstate_kyc_ix(authority, sender.owner(), 3, expires_at);
+    let result = send(&mut env.ctx, &[ix], &[]).await;
+    assert_custom_error(result, hook::ErrorCode::KycNotRevoked.into());
+
+    let ix = revoke_kyc_ix(authority, sender.owner());
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+    let ix = reinstate_kyc_ix(authority, sender.owner(), 0, expires_at);
+    let result = send(&mut env.ctx, &[ix], &[]).await;
+    assert_custom_error(result, hook::ErrorCode::MissingReasonCode.into());
+
+    let ix = reinstate_kyc_ix(authority, sender.owner(), 3, expires_at);
+    send(&mut env.ctx, &[ix], &[]).await.unwrap();
+    env.transfer(&sender, &recipient, 10 * TOKENS)
+        .await
+        .unwrap();
+
+    let kyc = env.kyc(&sender.owner()).await;
+    assert_eq!(kyc.status, 1);
+    assert_eq!(kyc.level, KycLevel::Enhanced);
+    assert_eq!(kyc.expires_at, expires_at);
+    assert_eq!((kyc.revocation_count, kyc.reinstatement_count), (1, 1));
+    assert_eq!(kyc.reinstatement_reason, 3);
+    assert!(kyc.revoked_at.is_some() && kyc.reinstated_at.is_some());
+}
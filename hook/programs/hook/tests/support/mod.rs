@@ -0,0 +1,59 @@
+// Helpers shared by the hook's integration tests and the AMM's, which pull this
+// file in by path.
+
+use anchor_lang::solana_program::instruction::AccountMeta;
+use solana_program_test::BanksClient;
+use solana_sdk::pubkey::Pubkey;
+use spl_tlv_account_resolution::state::ExtraAccountMetaList;
+use spl_transfer_hook_interface::{
+    get_extra_account_metas_address,
+    instruction::{execute, ExecuteInstruction},
+};
+use std::collections::HashMap;
+
+// Accounts Token-2022 needs after its own to invoke the hook for a transfer
+// authorized by `authority`, the owner or a delegate: the extras resolved from
+// the on-chain meta list the same way a wallet would, the list itself and the
+// hook program.
+pub async fn hook_accounts(
+    banks_client: &mut BanksClient,
+    source: &Pubkey,
+    mint: &Pubkey,
+    destination: &Pubkey,
+    authority: &Pubkey,
+    amount: u64,
+) -> Vec<AccountMeta> {
+    let validation = get_extra_account_metas_address(mint, &hook::ID);
+    let mut execute_ix = execute(
+        &hook::ID,
+        source,
+        mint,
+        destination,
+        authority,
+        &validation,
+        amount,
+    );
+
+    // Seeds may only read the base accounts, so those are all we fetch.
+    let mut datas = HashMap::new();
+    for meta in &execute_ix.accounts {
+        let account = banks_client.get_account(meta.pubkey).await.unwrap();
+        datas.insert(meta.pubkey, account.map(|account| account.data));
+    }
+    let validation_data = datas[&validation].clone().unwrap();
+    ExtraAccountMetaList::add_to_instruction::<ExecuteInstruction, _, _>(
+        &mut execute_ix,
+        |address| {
+            let data = datas.get(&address).cloned().flatten();
+            async move { Ok(data) }
+        },
+        &validation_data,
+    )
+    .await
+    .unwrap();
+
+    let mut accounts = execute_ix.accounts.split_off(5);
+    accounts.push(AccountMeta::new_readonly(validation, false));
+    accounts.push(AccountMeta::new_readonly(hook::ID, false));
+    accounts
+}
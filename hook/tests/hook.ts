@@ -38,8 +38,9 @@ describe("Hook Program", () => {
   let destinationAccount: PublicKey;
   let settings: PublicKey;
   let kycAccount: PublicKey;
+  let kycTombstone: PublicKey;
   let newKycAccount: PublicKey;
-  let mintLimits: PublicKey;
+  let mintConfig: PublicKey;
   let userUsage: PublicKey;
 
   before(async () => {
@@ -84,17 +85,23 @@ describe("Hook Program", () => {
     );
     kycAccount = kycPda;
 
+    const [kycTombstonePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("kyc-tombstone"), user.publicKey.toBuffer()],
+      program.programId
+    );
+    kycTombstone = kycTombstonePda;
+
     const [newKycPda] = PublicKey.findProgramAddressSync(
       [Buffer.from("kyc"), newUser.publicKey.toBuffer()],
       program.programId
     );
     newKycAccount = newKycPda;
 
-    const [mintLimitsPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("limits"), tokenMint.publicKey.toBuffer()],
+    const [mintConfigPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint-config"), tokenMint.publicKey.toBuffer()],
       program.programId
     );
-    mintLimits = mintLimitsPda;
+    mintConfig = mintConfigPda;
 
     const [userUsagePda] = PublicKey.findProgramAddressSync(
       [Buffer.from("usage"), user.publicKey.toBuffer(), tokenMint.publicKey.toBuffer()],
@@ -140,11 +147,13 @@ describe("Hook Program", () => {
 
   it("Should create KYC for a user", async () => {
     const tx = await program.methods
-      .createKyc()
+      .createKyc({ basic: {} }, new anchor.BN(Math.floor(Date.now() / 1000) + 365 * 24 * 60 * 60))
       .accounts({
         kyc: kycAccount,
         user: user.publicKey,
+        kycTombstone: kycTombstone,
         settings: settings,
+        roleAssignment: null,
         authority: authority.publicKey,
         systemProgram: SystemProgram.programId,
       })
@@ -157,6 +166,7 @@ describe("Hook Program", () => {
     const kycData = await program.account.kycData.fetch(kycAccount);
     assert(kycData.user.equals(user.publicKey));
     assert(kycData.status === 1); // approved
+    assert(kycData.level.basic !== undefined);
     assert(kycData.createdAt > 0);
     assert(kycData.revokedAt === null);
   });
@@ -207,26 +217,49 @@ describe("Hook Program", () => {
     const limits = {
       dailyLimit: new anchor.BN(1000000000), // 1 token per day
       transactionLimit: new anchor.BN(100000000), // 0.1 token per transaction
+      weeklyLimit: null,
+      monthlyLimit: null,
+      maxDailyTransfers: null,
+      cooldownSeconds: null,
     };
 
-    const tx = await program.methods
-      .setTransferLimits(limits)
+    // Limit rows live in the mint's config, one per KYC tier
+    await program.methods
+      .initializeMintConfig(authority.publicKey, {
+        requireSenderKyc: true,
+        requireRecipientKyc: false,
+        limitsEnabled: true,
+        maxBalance: null,
+        allowlistOnly: false,
+      })
       .accounts({
-        mintLimits: mintLimits,
+        mintConfig: mintConfig,
         mint: tokenMint.publicKey,
         settings: settings,
+        roleAssignment: null,
         authority: authority.publicKey,
         systemProgram: SystemProgram.programId,
-        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .signers([authority])
+      .rpc();
+
+    const tx = await program.methods
+      .setTransferLimits({ basic: {} }, limits)
+      .accounts({
+        mintConfig: mintConfig,
+        settings: settings,
+        roleAssignment: null,
+        authority: authority.publicKey,
       })
       .signers([authority])
       .rpc();
 
     console.log("Transfer limits set with signature:", tx);
 
-    // Verify mint limits
-    const limitsAccount = await program.account.mintLimits.fetch(mintLimits);
-    assert(limitsAccount.mint.equals(tokenMint.publicKey));
+    // Verify the Basic tier row
+    const configAccount = await program.account.mintConfig.fetch(mintConfig);
+    assert(configAccount.mint.equals(tokenMint.publicKey));
+    const limitsAccount = configAccount.tierLimits[0];
     assert(limitsAccount.dailyLimit.eq(limits.dailyLimit));
     assert(limitsAccount.transactionLimit.eq(limits.transactionLimit));
     assert(limitsAccount.isActive === true);
@@ -299,24 +332,41 @@ describe("Hook Program", () => {
   it("Should check transfer limits", async () => {
     const amount = new anchor.BN(50000000); // 0.05 tokens
 
+    await program.methods
+      .initUserUsage()
+      .accounts({
+        userUsage: userUsage,
+        mint: tokenMint.publicKey,
+        user: user.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+
     const tx = await program.methods
       .checkTransferLimits(amount)
       .accounts({
-        mintLimits: mintLimits,
+        kyc: kycAccount,
+        mintConfig: mintConfig,
         userUsage: userUsage,
         mint: tokenMint.publicKey,
         user: user.publicKey,
-        systemProgram: SystemProgram.programId,
       })
       .signers([user])
       .rpc();
 
     console.log("Transfer limits check passed with signature:", tx);
 
-    // Verify user usage was created
+    // Verify user usage was created; the check itself records nothing, only
+    // transfers count against the windows
     const usageAccount = await program.account.userUsage.fetch(userUsage);
     assert(usageAccount.user.equals(user.publicKey));
-    assert(usageAccount.lastResetDay > 0);
+    assert(usageAccount.mint.equals(tokenMint.publicKey));
+    const used = (buckets: anchor.BN[]) =>
+      buckets.reduce((sum, bucket) => sum.add(bucket), new anchor.BN(0));
+    assert(used(usageAccount.hourlyUsed).isZero());
+    assert(used(usageAccount.dailyUsed).isZero());
+    assert(usageAccount.currentDay.gtn(0));
   });
 
   it("Should revoke KYC for a user", async () => {
@@ -326,6 +376,7 @@ describe("Hook Program", () => {
         kyc: kycAccount,
         user: user.publicKey,
         settings: settings,
+        roleAssignment: null,
         authority: authority.publicKey,
       })
       .signers([authority])
@@ -360,24 +411,40 @@ describe("Hook Program", () => {
     }
   });
 
-  it("Should update program authority", async () => {
+  it("Should transfer program authority in two steps", async () => {
     const newAuthority = Keypair.generate();
     
-    const tx = await program.methods
-      .updateAuthority()
+    const proposeTx = await program.methods
+      .proposeAuthority(newAuthority.publicKey)
       .accounts({
         settings: settings,
         authority: authority.publicKey,
-        newAuthority: newAuthority.publicKey,
       })
       .signers([authority])
       .rpc();
 
-    console.log("Authority updated with signature:", tx);
+    console.log("Authority transfer proposed with signature:", proposeTx);
+
+    // Nothing changes until the new authority accepts
+    let settingsAccount = await program.account.programSettings.fetch(settings);
+    assert(settingsAccount.authority.equals(authority.publicKey));
+    assert(settingsAccount.pendingAuthority.equals(newAuthority.publicKey));
+
+    const acceptTx = await program.methods
+      .acceptAuthority()
+      .accounts({
+        settings: settings,
+        newAuthority: newAuthority.publicKey,
+      })
+      .signers([newAuthority])
+      .rpc();
+
+    console.log("Authority accepted with signature:", acceptTx);
 
     // Verify authority was updated
-    const settingsAccount = await program.account.programSettings.fetch(settings);
+    settingsAccount = await program.account.programSettings.fetch(settings);
     assert(settingsAccount.authority.equals(newAuthority.publicKey));
+    assert(settingsAccount.pendingAuthority === null);
     assert(settingsAccount.updatedAt > 0);
   });
 
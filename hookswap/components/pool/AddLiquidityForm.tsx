@@ -131,7 +131,7 @@ export function AddLiquidityForm({ onAddLiquidity, isLoading, connected }: AddLi
 
       // Call AMM program to add liquidity
       const addLiquidityTx = await (ammProgram.methods as any)
-        .addLiquidity(tokenAmount, 0) // min_lp_tokens = 0 for now
+        .addLiquidity(tokenAmount, solAmount, 0) // solAmount caps the SOL taken; min_lp_tokens = 0 for now
         .accounts({
           pool: poolPda,
           tokenMint: new PublicKey(tokenA.address),
@@ -147,15 +147,6 @@ export function AddLiquidityForm({ onAddLiquidity, isLoading, connected }: AddLi
         .preInstructions([createLpAccountInstruction])
         .transaction();
 
-      // Add SOL transfer instruction
-      const transferSolInstruction = SystemProgram.transfer({
-        fromPubkey: publicKey,
-        toPubkey: solVaultPda,
-        lamports: solAmount,
-      });
-
-      addLiquidityTx.add(transferSolInstruction);
-
       // Send transaction
       const signature = await sendTransaction(addLiquidityTx, connection);
       await connection.confirmTransaction(signature, 'confirmed');
@@ -126,7 +126,7 @@ export function CreatePool() {
 
       // Add initial liquidity
       const addLiquidityTx = await (ammProgram.methods as any)
-        .addLiquidity(tokenAmountLamports, 0) // min_lp_tokens = 0 for first deposit
+        .addLiquidity(tokenAmountLamports, solAmountLamports, 0) // min_lp_tokens = 0 for first deposit
         .accounts({
           pool: poolPda,
           tokenMint,
@@ -141,15 +141,6 @@ export function CreatePool() {
         })
         .transaction();
 
-      // Add SOL transfer instruction
-      const transferSolInstruction = SystemProgram.transfer({
-        fromPubkey: publicKey,
-        toPubkey: solVaultPda,
-        lamports: solAmountLamports,
-      });
-
-      addLiquidityTx.add(transferSolInstruction);
-
       // Send liquidity transaction
       const liquiditySignature = await sendTransaction(addLiquidityTx, connection);
       await connection.confirmTransaction(liquiditySignature, 'confirmed');
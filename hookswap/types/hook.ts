@@ -4,21 +4,41 @@ export interface HookProgramSettings {
   createdAt: number;
   isActive: boolean;
   updatedAt: number;
+  pendingAuthority: string | null; // proposed, awaiting accept_authority
 }
 
 // KYC data structure
 export interface KycData {
   user: string;
   status: number; // 0 = revoked, 1 = approved
+  level: 'basic' | 'enhanced' | 'institutional';
   createdAt: number;
-  revokedAt?: number;
+  expiresAt: number;
+  revokedAt?: number; // most recent revocation
+  revocationCount: number;
+  reinstatementCount: number;
+  reinstatedAt?: number; // most recent reinstatement
+  reinstatementReason: number;
+  country: string; // ISO 3166-1 alpha-2, empty until set
+  accredited: boolean;
 }
 
-// Mint transfer limits
+// Left behind by closeKyc; the user's record cannot be recreated
+export interface KycTombstoneData {
+  user: string;
+  revocationCount: number;
+  reinstatementCount: number;
+  closedAt: number;
+}
+
+// Transfer limits for one KYC tier of a mint
 export interface MintLimits {
-  mint: string;
-  dailyLimit: string;
+  dailyLimit: string; // rolling 24 hours
   transactionLimit: string;
+  weeklyLimit: string | null; // rolling 7 days
+  monthlyLimit: string | null; // rolling 30 days
+  maxDailyTransfers: number | null; // rolling 24 hours
+  cooldownSeconds: number | null;
   isActive: boolean;
   updatedAt: number;
 }
@@ -26,15 +46,65 @@ export interface MintLimits {
 // User usage tracking
 export interface UserUsageData {
   user: string;
-  dailyUsed: string;
-  lastResetDay: number;
+  mint: string;
+  hourlyUsed: string[]; // 24 hourly buckets
+  hourlyTransfers: number[];
+  currentHour: number;
+  dailyUsed: string[]; // 30 daily buckets
+  currentDay: number;
   lastTransaction: number;
 }
 
+// Per-mint hook configuration
+export interface MintConfigData {
+  mint: string;
+  authority: string;
+  requireSenderKyc: boolean;
+  requireRecipientKyc: boolean;
+  limitsEnabled: boolean;
+  maxBalance: string | null; // per token account, not per owner; vaults and treasuries exempt
+  allowlistOnly: boolean; // only allowlisted owners may send or receive; delegates are not checked
+  isPaused: boolean;
+  updatedAt: number;
+  tierLimits: MintLimits[]; // basic, enhanced, institutional
+}
+
+// Tokens a token account must keep until they vest: all of `amount` before
+// the cliff, then linearly from `startAt` to `endAt`
+export interface LockupData {
+  tokenAccount: string;
+  mint: string;
+  amount: string;
+  startAt: number;
+  cliffAt: number;
+  endAt: number;
+  createdAt: number;
+}
+
+// Owner allowed to hold a mint in allowlist-only mode
+export interface AllowlistEntryData {
+  mint: string;
+  wallet: string;
+  addedAt: number;
+}
+
+// Per-mint jurisdiction restrictions
+export interface JurisdictionPolicyData {
+  mint: string;
+  mode: 'blocklist' | 'allowlist';
+  countries: string[];
+  requireAccredited: boolean;
+  updatedAt: number;
+}
+
 // Transfer limits configuration
 export interface TransferLimitsConfig {
   dailyLimit: string;
   transactionLimit: string;
+  weeklyLimit?: string;
+  monthlyLimit?: string;
+  maxDailyTransfers?: number;
+  cooldownSeconds?: number;
 }
 
 // Hook validation result
@@ -50,7 +120,7 @@ export interface HookValidationResult {
 
 // Hook program authority actions
 export interface HookAuthorityAction {
-  action: 'create_kyc' | 'revoke_kyc' | 'update_authority' | 'set_transfer_limits';
+  action: 'create_kyc' | 'revoke_kyc' | 'propose_authority' | 'accept_authority' | 'set_transfer_limits' | 'update_transfer_limits';
   target: string;
   params?: any;
   timestamp: number;
@@ -59,7 +129,7 @@ export interface HookAuthorityAction {
 
 // Hook program events
 export interface HookEvent {
-  type: 'kyc_created' | 'kyc_revoked' | 'authority_updated' | 'limits_set' | 'transfer_validated';
+  type: 'kyc_created' | 'kyc_revoked' | 'authority_updated' | 'limits_set' | 'limits_updated' | 'transfer_validated';
   data: any;
   timestamp: number;
   signature: string;
@@ -22,6 +22,8 @@ export interface Pool {
   feeDenominator: number;
   createdAt: number;
   isActive: boolean;
+  solVaultBump: number;
+  locked: boolean;
 }
 
 // Pool creation parameters
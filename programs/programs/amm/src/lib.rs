@@ -1,11 +1,20 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
 use anchor_lang::solana_program::program::invoke_signed;
 use anchor_lang::solana_program::system_instruction;
+use anchor_spl::token::spl_token::native_mint;
 use anchor_spl::token_interface::{
     Mint, TokenAccount, TokenInterface,
-    transfer_checked, TransferChecked, MintTo, mint_to, burn, Burn,
+    MintTo, mint_to, burn, Burn,
 };
-use std::cmp;
+use spl_tlv_account_resolution::state::ExtraAccountMetaList;
+use spl_token_2022::extension::{transfer_hook, StateWithExtensions};
+use spl_transfer_hook_interface::get_extra_account_metas_address;
+use spl_transfer_hook_interface::instruction::{execute, ExecuteInstruction};
+
+pub mod math;
+
+use math::MathError;
 
 declare_id!("3KeeJh4v2qeSPMWekPwskMPkYVVBhqinixmEnWVdZ9mU");
 
@@ -24,89 +33,70 @@ pub mod amm {
     use super::*;
 
     pub fn initialize_pool(ctx: Context<InitializePool>) -> Result<()> {
+        if let Some(hook_program) = transfer_hook_program_id(&ctx.accounts.token_mint.to_account_info())? {
+            require!(is_whitelisted_hook(&hook_program), ErrorCode::UnsupportedTransferHook);
+        }
+
         let pool = &mut ctx.accounts.pool;
         pool.token_mint = ctx.accounts.token_mint.key();
         pool.token_vault = ctx.accounts.token_vault.key();
         pool.sol_vault = ctx.accounts.sol_vault.key();
         pool.lp_mint = ctx.accounts.lp_mint.key();
         pool.bump = ctx.bumps.pool;
+        pool.sol_vault_bump = ctx.bumps.sol_vault;
         pool.fee_numerator = FEE_NUMERATOR;
         pool.fee_denominator = FEE_DENOMINATOR;
         pool.created_at = Clock::get()?.unix_timestamp;
         pool.is_active = true;
+        pool.locked = false;
 
         msg!("Pool initialized for token {} with transfer hook support", pool.token_mint);
         Ok(())
     }
 
-    pub fn add_liquidity(
-        ctx: Context<AddLiquidity>,
+    /// Deposits `token_amount` tokens with SOL at the pool ratio. The first
+    /// deposit sets the ratio with exactly `sol_amount` lamports; later ones fail
+    /// if the ratio calls for more than `sol_amount`.
+    pub fn add_liquidity<'info>(
+        ctx: Context<'_, '_, '_, 'info, AddLiquidity<'info>>,
         token_amount: u64,
+        sol_amount: u64,
         min_lp_tokens: u64,
     ) -> Result<()> {
-        // Transfer tokens from user -> token_vault
-        // The transfer hook will be automatically called by Token-2022 if configured
-        let token_transfer_ctx = TransferChecked {
-            from: ctx.accounts.user_token_account.to_account_info(),
-            mint: ctx.accounts.token_mint.to_account_info(),
-            to: ctx.accounts.token_vault.to_account_info(),
-            authority: ctx.accounts.user.to_account_info(),
-        };
+        lock_pool(&mut ctx.accounts.pool)?;
 
-        let cpi_ctx = CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            token_transfer_ctx,
-        );
-
-        transfer_checked(
-            cpi_ctx,
-            token_amount,
-            ctx.accounts.token_mint.decimals,
-        )?;
+        // Snapshot reserves before any funds move so the math matches quote_add_liquidity
+        let token_reserve = ctx.accounts.token_vault.amount;
+        let sol_reserve = ctx.accounts.sol_vault.lamports();
 
         // Calculate SOL amount and LP tokens to mint
-        let sol_amount: u64;
-        let lp_tokens_to_mint: u64;
-
-        if ctx.accounts.token_vault.amount == 0 {
+        let (sol_amount, lp_tokens_to_mint) = if token_reserve == 0 {
             // First deposit
-            sol_amount = ctx.accounts.user.lamports();
-            lp_tokens_to_mint = (token_amount as u128)
-                .checked_mul(1_000_000)
-                .unwrap_or(0) as u64;
+            require!(sol_amount > 0, ErrorCode::InsufficientLiquidity);
+            (sol_amount, math::initial_lp_for_deposit(token_amount)?)
         } else {
-            let token_reserve = ctx.accounts.token_vault.amount.saturating_sub(token_amount);
-            let sol_reserve = ctx.accounts.sol_vault.lamports();
-
-            require!(token_reserve > 0 && sol_reserve > 0, ErrorCode::InsufficientLiquidity);
-
-            sol_amount = (token_amount as u128)
-                .checked_mul(sol_reserve as u128)
-                .unwrap_or(0)
-                .checked_div(token_reserve as u128)
-                .unwrap_or(0) as u64;
-
-            let total_supply = ctx.accounts.lp_mint.supply;
-            lp_tokens_to_mint = if total_supply > 0 {
-                let token_ratio = (token_amount as u128)
-                    .checked_mul(total_supply as u128)
-                    .unwrap_or(0)
-                    .checked_div(token_reserve as u128)
-                    .unwrap_or(0) as u64;
-
-                let sol_ratio = (sol_amount as u128)
-                    .checked_mul(total_supply as u128)
-                    .unwrap_or(0)
-                    .checked_div(sol_reserve as u128)
-                    .unwrap_or(0) as u64;
-
-                cmp::min(token_ratio, sol_ratio)
-            } else {
-                (token_amount as u128)
-                    .checked_mul(1_000_000)
-                    .unwrap_or(0) as u64
-            };
-        }
+            let (required_sol, lp_tokens) = math::lp_for_deposit(
+                token_amount,
+                token_reserve,
+                sol_reserve,
+                ctx.accounts.lp_mint.supply,
+            )?;
+            require!(required_sol <= sol_amount, ErrorCode::SlippageExceeded);
+            (required_sol, lp_tokens)
+        };
+
+        // Transfer tokens from user -> token_vault
+        // The transfer hook will be automatically called by Token-2022 if configured
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            ctx.accounts.user_token_account.to_account_info(),
+            &ctx.accounts.token_mint,
+            ctx.accounts.token_vault.to_account_info(),
+            ctx.accounts.user.to_account_info(),
+            ctx.remaining_accounts,
+            token_amount,
+            &[],
+        )?;
 
         require!(
             lp_tokens_to_mint >= min_lp_tokens,
@@ -158,32 +148,25 @@ pub mod amm {
             lp_tokens_to_mint
         );
 
+        ctx.accounts.pool.locked = false;
+
         Ok(())
     }
 
-    pub fn remove_liquidity(
-        ctx: Context<RemoveLiquidity>,
+    pub fn remove_liquidity<'info>(
+        ctx: Context<'_, '_, '_, 'info, RemoveLiquidity<'info>>,
         lp_amount: u64,
         min_token_amount: u64,
         min_sol_amount: u64,
     ) -> Result<()> {
-        let pool_token_amount = ctx.accounts.token_vault.amount;
-        let pool_sol_amount = ctx.accounts.sol_vault.lamports();
-        let lp_supply = ctx.accounts.lp_mint.supply;
-
-        require!(lp_supply > 0, ErrorCode::InsufficientLiquidity);
+        lock_pool(&mut ctx.accounts.pool)?;
 
-        let token_amount = (pool_token_amount as u128)
-            .checked_mul(lp_amount as u128)
-            .unwrap_or(0)
-            .checked_div(lp_supply as u128)
-            .unwrap_or(0) as u64;
-
-        let sol_amount = (pool_sol_amount as u128)
-            .checked_mul(lp_amount as u128)
-            .unwrap_or(0)
-            .checked_div(lp_supply as u128)
-            .unwrap_or(0) as u64;
+        let (token_amount, sol_amount) = math::amounts_for_lp(
+            lp_amount,
+            ctx.accounts.token_vault.amount,
+            ctx.accounts.sol_vault.lamports(),
+            ctx.accounts.lp_mint.supply,
+        )?;
 
         require!(token_amount >= min_token_amount, ErrorCode::SlippageExceeded);
         require!(sol_amount >= min_sol_amount, ErrorCode::SlippageExceeded);
@@ -210,24 +193,26 @@ pub mod amm {
         ];
         let signer = &[&seeds[..]];
 
-        let token_transfer_ctx = CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            TransferChecked {
-                from: ctx.accounts.token_vault.to_account_info(),
-                mint: ctx.accounts.token_mint.to_account_info(),
-                to: ctx.accounts.user_token_account.to_account_info(),
-                authority: ctx.accounts.pool.to_account_info(),
-            },
-            signer,
-        );
-
-        transfer_checked(
-            token_transfer_ctx,
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            ctx.accounts.token_vault.to_account_info(),
+            &ctx.accounts.token_mint,
+            ctx.accounts.user_token_account.to_account_info(),
+            ctx.accounts.pool.to_account_info(),
+            ctx.remaining_accounts,
             token_amount,
-            ctx.accounts.token_mint.decimals,
+            signer,
         )?;
 
-        // Transfer SOL from pool -> user
+        // Transfer SOL from pool -> user (signed by sol_vault PDA)
+        let pool_key = ctx.accounts.pool.key();
+        let sol_vault_seeds = &[
+            b"vault".as_ref(),
+            pool_key.as_ref(),
+            native_mint::ID.as_ref(),
+            &[ctx.accounts.pool.sol_vault_bump],
+        ];
+
         let sol_ix = system_instruction::transfer(
             &ctx.accounts.sol_vault.key(),
             &ctx.accounts.user.key(),
@@ -241,7 +226,7 @@ pub mod amm {
                 ctx.accounts.user.to_account_info(),
                 ctx.accounts.system_program.to_account_info(),
             ],
-            signer,
+            &[&sol_vault_seeds[..]],
         )?;
 
         msg!(
@@ -251,64 +236,48 @@ pub mod amm {
             sol_amount
         );
 
+        ctx.accounts.pool.locked = false;
+
         Ok(())
     }
 
-    pub fn swap_token_for_sol(
-        ctx: Context<SwapToken>,
+    pub fn swap_token_for_sol<'info>(
+        ctx: Context<'_, '_, '_, 'info, SwapToken<'info>>,
         token_amount: u64,
         min_sol_out: u64,
     ) -> Result<()> {
-        // Transfer tokens from user -> token_vault
-        // The transfer hook will be automatically called by Token-2022 if configured
-        let token_transfer_ctx = TransferChecked {
-            from: ctx.accounts.user_token_account.to_account_info(),
-            mint: ctx.accounts.token_mint.to_account_info(),
-            to: ctx.accounts.token_vault.to_account_info(),
-            authority: ctx.accounts.user.to_account_info(),
-        };
+        lock_pool(&mut ctx.accounts.pool)?;
 
-        let cpi_ctx = CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            token_transfer_ctx,
-        );
-
-        transfer_checked(
-            cpi_ctx,
+        // Compute output SOL amount from the reserves before the deposit lands
+        let (sol_out_after_fee, fee) = ctx.accounts.pool.swap_output(
             token_amount,
-            ctx.accounts.token_mint.decimals,
+            ctx.accounts.token_vault.amount,
+            ctx.accounts.sol_vault.lamports(),
         )?;
 
-        // Compute output SOL amount (constant product formula)
-        let token_reserve = ctx.accounts.token_vault.amount.saturating_sub(token_amount);
-        let sol_reserve = ctx.accounts.sol_vault.lamports();
-
-        require!(token_reserve > 0 && sol_reserve > 0, ErrorCode::InsufficientLiquidity);
-
-        let sol_out = (sol_reserve as u128)
-            .checked_mul(token_amount as u128)
-            .unwrap_or(0)
-            .checked_div(token_reserve as u128)
-            .unwrap_or(0) as u64;
-
-        let fee = (sol_out as u128)
-            .checked_mul(FEE_NUMERATOR as u128)
-            .unwrap_or(0)
-            .checked_div(FEE_DENOMINATOR as u128)
-            .unwrap_or(0) as u64;
-
-        let sol_out_after_fee = sol_out.saturating_sub(fee);
-
         require!(sol_out_after_fee >= min_sol_out, ErrorCode::SlippageExceeded);
 
-        // Transfer SOL to user signed by pool PDA
-        let token_mint_key = ctx.accounts.token_mint.key();
-        let seeds = &[
-            b"pool".as_ref(),
-            token_mint_key.as_ref(),
-            &[ctx.accounts.pool.bump],
+        // Transfer tokens from user -> token_vault
+        // The transfer hook will be automatically called by Token-2022 if configured
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            ctx.accounts.user_token_account.to_account_info(),
+            &ctx.accounts.token_mint,
+            ctx.accounts.token_vault.to_account_info(),
+            ctx.accounts.user.to_account_info(),
+            ctx.remaining_accounts,
+            token_amount,
+            &[],
+        )?;
+
+        // Transfer SOL to user signed by sol_vault PDA
+        let pool_key = ctx.accounts.pool.key();
+        let sol_vault_seeds = &[
+            b"vault".as_ref(),
+            pool_key.as_ref(),
+            native_mint::ID.as_ref(),
+            &[ctx.accounts.pool.sol_vault_bump],
         ];
-        let signer = &[&seeds[..]];
 
         let sol_ix = system_instruction::transfer(
             &ctx.accounts.sol_vault.key(),
@@ -323,19 +292,32 @@ pub mod amm {
                 ctx.accounts.user.to_account_info(),
                 ctx.accounts.system_program.to_account_info(),
             ],
-            signer,
+            &[&sol_vault_seeds[..]],
         )?;
 
-        msg!("Swapped {} tokens for {} SOL (fee: {} SOL)", token_amount, sol_out_after_fee, fee);
+        msg!("Swapped {} tokens for {} SOL (fee: {} tokens)", token_amount, sol_out_after_fee, fee);
+
+        ctx.accounts.pool.locked = false;
 
         Ok(())
     }
 
-    pub fn swap_sol_for_token(
-        ctx: Context<SwapSol>,
+    pub fn swap_sol_for_token<'info>(
+        ctx: Context<'_, '_, '_, 'info, SwapSol<'info>>,
         lamport_amount: u64,
         min_token_out: u64,
     ) -> Result<()> {
+        lock_pool(&mut ctx.accounts.pool)?;
+
+        // Calculate token out from the reserves before the deposit lands
+        let (token_out_after_fee, fee) = ctx.accounts.pool.swap_output(
+            lamport_amount,
+            ctx.accounts.sol_vault.lamports(),
+            ctx.accounts.token_vault.amount,
+        )?;
+
+        require!(token_out_after_fee >= min_token_out, ErrorCode::SlippageExceeded);
+
         // Transfer SOL from user -> sol_vault
         let transfer_ix = system_instruction::transfer(
             &ctx.accounts.user.key(),
@@ -353,28 +335,6 @@ pub mod amm {
             &[],
         )?;
 
-        // Calculate token out
-        let sol_reserve = ctx.accounts.sol_vault.lamports().saturating_sub(lamport_amount);
-        let token_reserve = ctx.accounts.token_vault.amount;
-
-        require!(sol_reserve > 0 && token_reserve > 0, ErrorCode::InsufficientLiquidity);
-
-        let token_out = (token_reserve as u128)
-            .checked_mul(lamport_amount as u128)
-            .unwrap_or(0)
-            .checked_div(sol_reserve as u128)
-            .unwrap_or(0) as u64;
-
-        let fee = (token_out as u128)
-            .checked_mul(FEE_NUMERATOR as u128)
-            .unwrap_or(0)
-            .checked_div(FEE_DENOMINATOR as u128)
-            .unwrap_or(0) as u64;
-
-        let token_out_after_fee = token_out.saturating_sub(fee);
-
-        require!(token_out_after_fee >= min_token_out, ErrorCode::SlippageExceeded);
-
         // Transfer tokens to user (signed by pool PDA)
         // The transfer hook will be automatically called by Token-2022 if configured
         let token_mint_key = ctx.accounts.token_mint.key();
@@ -385,27 +345,201 @@ pub mod amm {
         ];
         let signer = &[&seeds[..]];
 
-        let token_transfer_ctx = CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            TransferChecked {
-                from: ctx.accounts.token_vault.to_account_info(),
-                mint: ctx.accounts.token_mint.to_account_info(),
-                to: ctx.accounts.user_token_account.to_account_info(),
-                authority: ctx.accounts.pool.to_account_info(),
-            },
-            signer,
-        );
-
-        transfer_checked(
-            token_transfer_ctx,
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            ctx.accounts.token_vault.to_account_info(),
+            &ctx.accounts.token_mint,
+            ctx.accounts.user_token_account.to_account_info(),
+            ctx.accounts.pool.to_account_info(),
+            ctx.remaining_accounts,
             token_out_after_fee,
-            ctx.accounts.token_mint.decimals,
+            signer,
         )?;
 
-        msg!("Swapped {} SOL for {} tokens (fee: {} tokens)", lamport_amount, token_out_after_fee, fee);
+        msg!("Swapped {} SOL for {} tokens (fee: {} lamports)", lamport_amount, token_out_after_fee, fee);
+
+        ctx.accounts.pool.locked = false;
 
         Ok(())
     }
+
+    /// Returns the output of a swap against the current reserves without executing it.
+    pub fn quote_swap(
+        ctx: Context<Quote>,
+        amount_in: u64,
+        direction: SwapDirection,
+    ) -> Result<SwapQuote> {
+        let token_reserve = ctx.accounts.token_vault.amount;
+        let sol_reserve = ctx.accounts.sol_vault.lamports();
+
+        let pool = &ctx.accounts.pool;
+        let (amount_out, fee) = match direction {
+            SwapDirection::TokenToSol => pool.swap_output(amount_in, token_reserve, sol_reserve)?,
+            SwapDirection::SolToToken => pool.swap_output(amount_in, sol_reserve, token_reserve)?,
+        };
+
+        Ok(SwapQuote {
+            amount_in,
+            amount_out,
+            fee,
+        })
+    }
+
+    /// Returns the SOL required and LP tokens minted for a deposit of `token_amount`.
+    /// An empty pool takes whatever SOL its first depositor brings, so its quote
+    /// has a `sol_amount` of 0.
+    pub fn quote_add_liquidity(ctx: Context<Quote>, token_amount: u64) -> Result<LiquidityQuote> {
+        let token_reserve = ctx.accounts.token_vault.amount;
+        let (sol_amount, lp_amount) = if token_reserve == 0 {
+            (0, math::initial_lp_for_deposit(token_amount)?)
+        } else {
+            math::lp_for_deposit(
+                token_amount,
+                token_reserve,
+                ctx.accounts.sol_vault.lamports(),
+                ctx.accounts.lp_mint.supply,
+            )?
+        };
+
+        Ok(LiquidityQuote {
+            token_amount,
+            sol_amount,
+            lp_amount,
+        })
+    }
+
+    /// Returns the tokens and SOL paid out for burning `lp_amount` LP tokens.
+    pub fn quote_remove_liquidity(ctx: Context<Quote>, lp_amount: u64) -> Result<LiquidityQuote> {
+        let (token_amount, sol_amount) = math::amounts_for_lp(
+            lp_amount,
+            ctx.accounts.token_vault.amount,
+            ctx.accounts.sol_vault.lamports(),
+            ctx.accounts.lp_mint.supply,
+        )?;
+
+        Ok(LiquidityQuote {
+            token_amount,
+            sol_amount,
+            lp_amount,
+        })
+    }
+}
+
+// Transfers tokens with `transfer_checked`, forwarding the transfer hook's extra
+// accounts from `remaining_accounts` so Token-2022 can invoke the mint's hook.
+#[allow(clippy::too_many_arguments)]
+fn transfer_tokens<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    from: AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    to: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let mint_info = mint.to_account_info();
+    let mut cpi_instruction = spl_token_2022::instruction::transfer_checked(
+        token_program.key,
+        from.key,
+        mint_info.key,
+        to.key,
+        authority.key,
+        &[],
+        amount,
+        mint.decimals,
+    )?;
+    let mut cpi_account_infos = vec![from, mint_info.clone(), to, authority];
+
+    if let Some(hook_program) = transfer_hook_program_id(&mint_info)? {
+        add_hook_accounts(
+            &mut cpi_instruction,
+            &mut cpi_account_infos,
+            &hook_program,
+            amount,
+            remaining_accounts,
+        )?;
+    }
+
+    invoke_signed(&cpi_instruction, &cpi_account_infos, signer_seeds)?;
+    Ok(())
+}
+
+// Appends the hook program, its extra account meta list and every extra account the
+// list declares. The metas are resolved against a mirror `Execute` instruction so PDA
+// seeds derive from the hook program, as Token-2022 will when it calls the hook.
+fn add_hook_accounts<'info>(
+    cpi_instruction: &mut Instruction,
+    cpi_account_infos: &mut Vec<AccountInfo<'info>>,
+    hook_program: &Pubkey,
+    amount: u64,
+    remaining_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
+    let find_account = |key: &Pubkey| {
+        remaining_accounts
+            .iter()
+            .find(|info| info.key == key)
+            .cloned()
+            .ok_or(error!(ErrorCode::MissingHookAccount))
+    };
+
+    let mint_key = cpi_account_infos[1].key;
+    let validation_key = get_extra_account_metas_address(mint_key, hook_program);
+    let validation_info = find_account(&validation_key)?;
+    let program_info = find_account(hook_program)?;
+
+    let mut execute_instruction = execute(
+        hook_program,
+        cpi_account_infos[0].key,
+        mint_key,
+        cpi_account_infos[2].key,
+        cpi_account_infos[3].key,
+        &validation_key,
+        amount,
+    );
+    let mut execute_account_infos = cpi_account_infos.clone();
+    execute_account_infos.push(validation_info.clone());
+
+    ExtraAccountMetaList::add_to_cpi_instruction::<ExecuteInstruction>(
+        &mut execute_instruction,
+        &mut execute_account_infos,
+        &validation_info.try_borrow_data()?,
+        remaining_accounts,
+    )?;
+
+    // Everything after source, mint, destination, owner and the validation account
+    // is an extra account the hook asked for.
+    let extra_count = execute_instruction.accounts.len() - 5;
+    cpi_instruction
+        .accounts
+        .extend(execute_instruction.accounts.into_iter().skip(5));
+    cpi_account_infos.extend(execute_account_infos.into_iter().skip(5).take(extra_count));
+
+    cpi_instruction
+        .accounts
+        .push(AccountMeta::new_readonly(validation_key, false));
+    cpi_instruction
+        .accounts
+        .push(AccountMeta::new_readonly(*hook_program, false));
+    cpi_account_infos.push(validation_info);
+    cpi_account_infos.push(program_info);
+    Ok(())
+}
+
+// Marks the pool busy for the rest of the instruction. The flag is written to the
+// account straight away so a hook program re-entering the AMM during a token
+// transfer CPI sees it; handlers clear it before returning.
+fn lock_pool(pool: &mut Account<Pool>) -> Result<()> {
+    require!(!pool.locked, ErrorCode::PoolLocked);
+    pool.locked = true;
+    pool.exit(&crate::ID)
+}
+
+// Transfer hook program configured on the mint, if any
+fn transfer_hook_program_id(mint: &AccountInfo) -> Result<Option<Pubkey>> {
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    Ok(transfer_hook::get_program_id(&mint))
 }
 
 // Helper function to check if a transfer hook is whitelisted
@@ -420,7 +554,7 @@ pub struct InitializePool<'info> {
     #[account(
         init,
         payer = payer,
-        space = 8 + 32 + 32 + 32 + 32 + 1 + 8 + 8 + 8 + 1, // Add space for lp_mint, fee settings, created_at, is_active
+        space = 8 + 32 + 32 + 32 + 32 + 1 + 8 + 8 + 8 + 1 + 1 + 1, // Add space for lp_mint, fee settings, created_at, is_active, sol_vault_bump, locked
         seeds = [b"pool", token_mint.key().as_ref()],
         bump
     )]
@@ -438,8 +572,12 @@ pub struct InitializePool<'info> {
     )]
     pub token_vault: InterfaceAccount<'info, TokenAccount>,
 
-    /// CHECK: This is a native SOL account
-    #[account(mut)]
+    /// CHECK: System-owned PDA holding the pool's native SOL
+    #[account(
+        mut,
+        seeds = [b"vault", pool.key().as_ref(), native_mint::ID.as_ref()],
+        bump
+    )]
     pub sol_vault: AccountInfo<'info>,
 
     #[account(
@@ -491,6 +629,7 @@ pub struct AddLiquidity<'info> {
 
     #[account(
         mut,
+        constraint = user_token_account.key() != token_vault.key() @ ErrorCode::TokenVaultAliased,
         constraint = user_token_account.mint == token_mint.key() @ ErrorCode::InvalidMint,
         constraint = user_token_account.owner == user.key() @ ErrorCode::InvalidOwner
     )]
@@ -542,6 +681,7 @@ pub struct RemoveLiquidity<'info> {
 
     #[account(
         mut,
+        constraint = user_token_account.key() != token_vault.key() @ ErrorCode::TokenVaultAliased,
         constraint = user_token_account.mint == token_mint.key() @ ErrorCode::InvalidMint,
         constraint = user_token_account.owner == user.key() @ ErrorCode::InvalidOwner
     )]
@@ -564,6 +704,7 @@ pub struct RemoveLiquidity<'info> {
 #[derive(Accounts)]
 pub struct SwapToken<'info> {
     #[account(
+        mut,
         seeds = [b"pool", token_mint.key().as_ref()],
         bump = pool.bump
     )]
@@ -586,6 +727,7 @@ pub struct SwapToken<'info> {
 
     #[account(
         mut,
+        constraint = user_token_account.key() != token_vault.key() @ ErrorCode::TokenVaultAliased,
         constraint = user_token_account.mint == token_mint.key() @ ErrorCode::InvalidMint,
         constraint = user_token_account.owner == user.key() @ ErrorCode::InvalidOwner
     )]
@@ -601,6 +743,7 @@ pub struct SwapToken<'info> {
 #[derive(Accounts)]
 pub struct SwapSol<'info> {
     #[account(
+        mut,
         seeds = [b"pool", token_mint.key().as_ref()],
         bump = pool.bump
     )]
@@ -623,6 +766,7 @@ pub struct SwapSol<'info> {
 
     #[account(
         mut,
+        constraint = user_token_account.key() != token_vault.key() @ ErrorCode::TokenVaultAliased,
         constraint = user_token_account.mint == token_mint.key() @ ErrorCode::InvalidMint,
         constraint = user_token_account.owner == user.key() @ ErrorCode::InvalidOwner
     )]
@@ -635,6 +779,34 @@ pub struct SwapSol<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct Quote<'info> {
+    #[account(
+        seeds = [b"pool", token_mint.key().as_ref()],
+        bump = pool.bump,
+        constraint = !pool.locked @ ErrorCode::PoolLocked
+    )]
+    pub pool: Account<'info, Pool>,
+
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        constraint = token_vault.key() == pool.token_vault @ ErrorCode::InvalidVault
+    )]
+    pub token_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: This is a native SOL account
+    #[account(
+        constraint = sol_vault.key() == pool.sol_vault @ ErrorCode::InvalidVault
+    )]
+    pub sol_vault: AccountInfo<'info>,
+
+    #[account(
+        constraint = lp_mint.key() == pool.lp_mint @ ErrorCode::InvalidMint
+    )]
+    pub lp_mint: InterfaceAccount<'info, Mint>,
+}
+
 #[account]
 pub struct Pool {
     pub token_mint: Pubkey,
@@ -646,6 +818,46 @@ pub struct Pool {
     pub fee_denominator: u64,
     pub created_at: i64,
     pub is_active: bool,
+    pub sol_vault_bump: u8,
+    pub locked: bool, // Set while a mutating instruction is in flight
+}
+
+impl Pool {
+    // Output of a swap of `amount_in` against the given reserves at this pool's fee,
+    // returned as (amount_out, fee) with the fee denominated in the input asset.
+    // Shared by the swap handlers and quote_swap so simulations match execution.
+    pub fn swap_output(&self, amount_in: u64, reserve_in: u64, reserve_out: u64) -> Result<(u64, u64)> {
+        let amount_out = math::get_amount_out(
+            amount_in,
+            reserve_in,
+            reserve_out,
+            self.fee_numerator,
+            self.fee_denominator,
+        )?;
+        let fee = math::swap_fee(amount_in, self.fee_numerator, self.fee_denominator)?;
+        Ok((amount_out, fee))
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum SwapDirection {
+    TokenToSol,
+    SolToToken,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SwapQuote {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    /// Fee charged, denominated in the input asset
+    pub fee: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LiquidityQuote {
+    pub token_amount: u64,
+    pub sol_amount: u64,
+    pub lp_amount: u64,
 }
 
 #[error_code]
@@ -667,4 +879,29 @@ pub enum ErrorCode {
 
     #[msg("Unsupported transfer hook program")]
     UnsupportedTransferHook,
+
+    #[msg("Math overflow")]
+    MathOverflow,
+
+    #[msg("Invalid fee settings")]
+    InvalidFee,
+
+    #[msg("Transfer hook account missing from remaining accounts")]
+    MissingHookAccount,
+
+    #[msg("Pool is locked by an instruction in progress")]
+    PoolLocked,
+
+    #[msg("User token account cannot be the pool token vault")]
+    TokenVaultAliased,
+}
+
+impl From<MathError> for Error {
+    fn from(err: MathError) -> Self {
+        match err {
+            MathError::MathOverflow => error!(ErrorCode::MathOverflow),
+            MathError::InsufficientLiquidity => error!(ErrorCode::InsufficientLiquidity),
+            MathError::InvalidFee => error!(ErrorCode::InvalidFee),
+        }
+    }
 }
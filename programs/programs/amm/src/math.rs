@@ -0,0 +1,188 @@
+//! Pure pricing math for the constant product pool.
+//!
+//! Everything here works on plain integers and only depends on `core`, so the
+//! same functions can be used on-chain by the handlers and off-chain by clients
+//! that want the exact answer the program will compute.
+
+use core::cmp;
+
+/// LP tokens minted per token on the first deposit into an empty pool.
+pub const INITIAL_LP_MULTIPLIER: u64 = 1_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MathError {
+    /// An intermediate or final value did not fit its integer type.
+    MathOverflow,
+    /// The pool has no reserves (or not enough) to serve the request.
+    InsufficientLiquidity,
+    /// The fee settings are not a valid fraction below one.
+    InvalidFee,
+}
+
+pub type MathResult<T> = core::result::Result<T, MathError>;
+
+fn check_fee(fee_numerator: u64, fee_denominator: u64) -> MathResult<()> {
+    if fee_denominator == 0 || fee_numerator >= fee_denominator {
+        return Err(MathError::InvalidFee);
+    }
+    Ok(())
+}
+
+fn to_u64(value: u128) -> MathResult<u64> {
+    u64::try_from(value).map_err(|_| MathError::MathOverflow)
+}
+
+fn div_ceil(numerator: u128, denominator: u128) -> MathResult<u128> {
+    let quotient = numerator
+        .checked_div(denominator)
+        .ok_or(MathError::MathOverflow)?;
+    // quotient * denominator <= numerator, so this cannot overflow
+    if quotient * denominator == numerator {
+        Ok(quotient)
+    } else {
+        quotient.checked_add(1).ok_or(MathError::MathOverflow)
+    }
+}
+
+/// Fee charged on `amount_in`, rounded up so the pool never undercharges.
+pub fn swap_fee(amount_in: u64, fee_numerator: u64, fee_denominator: u64) -> MathResult<u64> {
+    check_fee(fee_numerator, fee_denominator)?;
+
+    let fee = div_ceil(
+        (amount_in as u128)
+            .checked_mul(fee_numerator as u128)
+            .ok_or(MathError::MathOverflow)?,
+        fee_denominator as u128,
+    )?;
+
+    to_u64(fee)
+}
+
+/// Output received for swapping `amount_in` into the pool, with the fee taken
+/// from the input. Rounds down.
+pub fn get_amount_out(
+    amount_in: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> MathResult<u64> {
+    check_fee(fee_numerator, fee_denominator)?;
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(MathError::InsufficientLiquidity);
+    }
+
+    let amount_in_with_fee = (amount_in as u128)
+        .checked_mul((fee_denominator - fee_numerator) as u128)
+        .ok_or(MathError::MathOverflow)?;
+
+    let numerator = amount_in_with_fee
+        .checked_mul(reserve_out as u128)
+        .ok_or(MathError::MathOverflow)?;
+
+    let denominator = (reserve_in as u128)
+        .checked_mul(fee_denominator as u128)
+        .ok_or(MathError::MathOverflow)?
+        .checked_add(amount_in_with_fee)
+        .ok_or(MathError::MathOverflow)?;
+
+    to_u64(numerator / denominator)
+}
+
+/// Input required to receive exactly `amount_out` from the pool, fee included.
+/// Rounds up.
+pub fn get_amount_in(
+    amount_out: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> MathResult<u64> {
+    check_fee(fee_numerator, fee_denominator)?;
+    if reserve_in == 0 || amount_out >= reserve_out {
+        return Err(MathError::InsufficientLiquidity);
+    }
+
+    let numerator = (reserve_in as u128)
+        .checked_mul(amount_out as u128)
+        .ok_or(MathError::MathOverflow)?
+        .checked_mul(fee_denominator as u128)
+        .ok_or(MathError::MathOverflow)?;
+
+    let denominator = ((reserve_out - amount_out) as u128)
+        .checked_mul((fee_denominator - fee_numerator) as u128)
+        .ok_or(MathError::MathOverflow)?;
+
+    to_u64(div_ceil(numerator, denominator)?)
+}
+
+/// LP tokens minted for the first deposit into an empty pool.
+pub fn initial_lp_for_deposit(token_amount: u64) -> MathResult<u64> {
+    token_amount
+        .checked_mul(INITIAL_LP_MULTIPLIER)
+        .ok_or(MathError::MathOverflow)
+}
+
+/// SOL required alongside `token_amount` to keep the pool ratio, and the LP
+/// tokens minted for the pair, returned as `(sol_amount, lp_tokens)`.
+///
+/// The SOL side rounds up and the LP side rounds down so a depositor can never
+/// extract value from rounding.
+pub fn lp_for_deposit(
+    token_amount: u64,
+    token_reserve: u64,
+    sol_reserve: u64,
+    lp_supply: u64,
+) -> MathResult<(u64, u64)> {
+    if token_reserve == 0 || sol_reserve == 0 {
+        return Err(MathError::InsufficientLiquidity);
+    }
+
+    let sol_amount = to_u64(div_ceil(
+        (token_amount as u128)
+            .checked_mul(sol_reserve as u128)
+            .ok_or(MathError::MathOverflow)?,
+        token_reserve as u128,
+    )?)?;
+
+    if lp_supply == 0 {
+        return Ok((sol_amount, initial_lp_for_deposit(token_amount)?));
+    }
+
+    let token_share = (token_amount as u128)
+        .checked_mul(lp_supply as u128)
+        .ok_or(MathError::MathOverflow)?
+        / token_reserve as u128;
+
+    let sol_share = (sol_amount as u128)
+        .checked_mul(lp_supply as u128)
+        .ok_or(MathError::MathOverflow)?
+        / sol_reserve as u128;
+
+    Ok((sol_amount, to_u64(cmp::min(token_share, sol_share))?))
+}
+
+/// Tokens and SOL paid out for burning `lp_amount`, returned as
+/// `(token_amount, sol_amount)`. Both sides round down.
+pub fn amounts_for_lp(
+    lp_amount: u64,
+    token_reserve: u64,
+    sol_reserve: u64,
+    lp_supply: u64,
+) -> MathResult<(u64, u64)> {
+    if lp_supply == 0 || lp_amount > lp_supply {
+        return Err(MathError::InsufficientLiquidity);
+    }
+
+    let token_amount = (token_reserve as u128)
+        .checked_mul(lp_amount as u128)
+        .ok_or(MathError::MathOverflow)?
+        / lp_supply as u128;
+
+    let sol_amount = (sol_reserve as u128)
+        .checked_mul(lp_amount as u128)
+        .ok_or(MathError::MathOverflow)?
+        / lp_supply as u128;
+
+    Ok((to_u64(token_amount)?, to_u64(sol_amount)?))
+}
@@ -0,0 +1,969 @@
+use amm::{LiquidityQuote, Pool, SwapDirection, SwapQuote};
+use anchor_lang::{
+    solana_program::{instruction::Instruction, program_pack::Pack},
+    AccountDeserialize, AnchorDeserialize, InstructionData, ToAccountMetas,
+};
+use anchor_spl::token::spl_token::native_mint;
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, InstructionError},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction, system_program, sysvar,
+    transaction::{Transaction, TransactionError},
+};
+use spl_tlv_account_resolution::{account::ExtraAccountMeta, state::ExtraAccountMetaList};
+use spl_token_2022::{
+    extension::{transfer_hook, ExtensionType},
+    state::{Account as TokenAccount, Mint},
+};
+use spl_transfer_hook_interface::instruction::{ExecuteInstruction, TransferHookInstruction};
+
+// The transfer hook's extra-account resolver, shared with the hook's own tests
+#[path = "../../../../hook/programs/hook/tests/support/mod.rs"]
+mod support;
+
+const DECIMALS: u8 = 9;
+const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+const TOKENS: u64 = 1_000_000_000;
+
+// Anchor's generated entrypoints tie the accounts slice to the account lifetime,
+// which the program-test processor signature cannot express.
+fn amm_processor(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    amm::entry(program_id, accounts, data)
+}
+
+fn hook_processor(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    hook::entry(program_id, accounts, data)
+}
+
+// Stands in for a compromised hook: an `Execute` that was handed the AMM program
+// calls straight back into it, and everything else goes to the real hook. The
+// runtime refuses a CPI back into a program already on the stack, so this calls
+// the AMM's entrypoint in-process to reach the pool lock behind that check.
+fn reentrant_hook_processor(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let is_execute = matches!(TransferHookInstruction::unpack(data), Ok(TransferHookInstruction::Execute { .. }));
+    if !is_execute || !accounts.iter().any(|account| account.key == &amm::ID) {
+        return hook_processor(program_id, accounts, data);
+    }
+
+    // Source, mint, destination, owner and validation, then the pool, SOL vault
+    // and LP mint from `point_hook_at_pool`; reordered to match `Quote`.
+    let quote_accounts = [5, 1, 2, 6, 7].map(|index| accounts[index].clone());
+    let quote = amm::instruction::QuoteSwap {
+        amount_in: 1,
+        direction: SwapDirection::TokenToSol,
+    };
+    amm_processor(&amm::ID, &quote_accounts, &quote.data())
+}
+
+// `cargo test-sbf` runs against the built amm.so and hook.so, under the real
+// heap, stack and compute limits; a plain `cargo test` runs both natively.
+fn program_test() -> ProgramTest {
+    let mut program_test = ProgramTest::default();
+    program_test.prefer_bpf(cfg!(feature = "test-sbf"));
+    program_test.add_program("amm", amm::ID, processor!(amm_processor));
+    program_test.add_program("hook", hook::ID, processor!(hook_processor));
+    program_test
+}
+
+struct Env {
+    ctx: ProgramTestContext,
+    mint: Pubkey,
+    pool: Pubkey,
+    token_vault: Pubkey,
+    sol_vault: Pubkey,
+    lp_mint: Pubkey,
+}
+
+struct User {
+    keypair: Keypair,
+    token_account: Pubkey,
+    lp_token_account: Pubkey,
+}
+
+fn settings_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"settings"], &hook::ID).0
+}
+
+fn kyc_address(user: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"kyc", user.as_ref()], &hook::ID).0
+}
+
+fn exempt_address(address: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"exempt", address.as_ref()], &hook::ID).0
+}
+
+fn mint_config_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"mint-config", mint.as_ref()], &hook::ID).0
+}
+
+fn usage_address(user: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"usage", user.as_ref(), mint.as_ref()], &hook::ID).0
+}
+
+fn lockup_address(token_account: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"lockup", token_account.as_ref()], &hook::ID).0
+}
+
+fn jurisdiction_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"jurisdiction", mint.as_ref()], &hook::ID).0
+}
+
+fn allowlist_address(mint: &Pubkey, wallet: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"allow", mint.as_ref(), wallet.as_ref()], &hook::ID).0
+}
+
+fn extra_account_metas_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"extra-account-metas", mint.as_ref()], &hook::ID).0
+}
+
+fn pool_addresses(mint: &Pubkey) -> (Pubkey, Pubkey, Pubkey) {
+    let pool = Pubkey::find_program_address(&[b"pool", mint.as_ref()], &amm::ID).0;
+    let token_vault =
+        Pubkey::find_program_address(&[b"vault", pool.as_ref(), mint.as_ref()], &amm::ID).0;
+    let sol_vault =
+        Pubkey::find_program_address(&[b"vault", pool.as_ref(), native_mint::ID.as_ref()], &amm::ID)
+            .0;
+    (pool, token_vault, sol_vault)
+}
+
+async fn send(
+    ctx: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let blockhash = ctx.banks_client.get_latest_blockhash().await.unwrap();
+    let mut all_signers = vec![&ctx.payer];
+    all_signers.extend_from_slice(signers);
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&ctx.payer.pubkey()),
+        &all_signers,
+        blockhash,
+    );
+    ctx.banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+fn assert_custom_error(result: Result<(), TransactionError>, code: u32) {
+    match result {
+        Err(TransactionError::InstructionError(_, InstructionError::Custom(actual))) => {
+            assert_eq!(actual, code)
+        }
+        other => panic!("expected custom error {code}, got {other:?}"),
+    }
+}
+
+async fn create_hooked_mint(ctx: &mut ProgramTestContext, hook_program: Pubkey) -> Pubkey {
+    let mint = Keypair::new();
+    let space = ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::TransferHook])
+        .unwrap();
+    let rent = ctx.banks_client.get_rent().await.unwrap();
+    let payer = ctx.payer.pubkey();
+
+    send(
+        ctx,
+        &[
+            system_instruction::create_account(
+                &payer,
+                &mint.pubkey(),
+                rent.minimum_balance(space),
+                space as u64,
+                &spl_token_2022::id(),
+            ),
+            transfer_hook::instruction::initialize(
+                &spl_token_2022::id(),
+                &mint.pubkey(),
+                Some(payer),
+                Some(hook_program),
+            )
+            .unwrap(),
+            spl_token_2022::instruction::initialize_mint2(
+                &spl_token_2022::id(),
+                &mint.pubkey(),
+                &payer,
+                None,
+                DECIMALS,
+            )
+            .unwrap(),
+        ],
+        &[&mint],
+    )
+    .await
+    .unwrap();
+
+    mint.pubkey()
+}
+
+async fn create_token_account(
+    ctx: &mut ProgramTestContext,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    extensions: &[ExtensionType],
+) -> Pubkey {
+    let account = Keypair::new();
+    let space = ExtensionType::try_calculate_account_len::<TokenAccount>(extensions).unwrap();
+    let rent = ctx.banks_client.get_rent().await.unwrap();
+    let payer = ctx.payer.pubkey();
+
+    send(
+        ctx,
+        &[
+            system_instruction::create_account(
+                &payer,
+                &account.pubkey(),
+                rent.minimum_balance(space),
+                space as u64,
+                &spl_token_2022::id(),
+            ),
+            spl_token_2022::instruction::initialize_account3(
+                &spl_token_2022::id(),
+                &account.pubkey(),
+                mint,
+                owner,
+            )
+            .unwrap(),
+        ],
+        &[&account],
+    )
+    .await
+    .unwrap();
+
+    account.pubkey()
+}
+
+async fn token_balance(ctx: &mut ProgramTestContext, account: &Pubkey) -> u64 {
+    let account = ctx.banks_client.get_account(*account).await.unwrap().unwrap();
+    TokenAccount::unpack_from_slice(&account.data[..TokenAccount::LEN])
+        .unwrap()
+        .amount
+}
+
+async fn hook_initialize(ctx: &mut ProgramTestContext) {
+    let authority = ctx.payer.pubkey();
+    let ix = Instruction {
+        program_id: hook::ID,
+        accounts: hook::accounts::Initialize {
+            settings: settings_address(),
+            authority,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: hook::instruction::Initialize {}.data(),
+    };
+    send(ctx, &[ix], &[]).await.unwrap();
+}
+
+async fn hook_create_kyc(ctx: &mut ProgramTestContext, user: &Pubkey) {
+    let authority = ctx.payer.pubkey();
+    let ix = Instruction {
+        program_id: hook::ID,
+        accounts: hook::accounts::CreateKyc {
+            kyc: kyc_address(user),
+            user: *user,
+            kyc_tombstone: Pubkey::find_program_address(&[b"kyc-tombstone", user.as_ref()], &hook::ID).0,
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: hook::instruction::CreateKyc {
+            level: hook::KycLevel::Basic,
+            expires_at: i64::MAX,
+        }
+        .data(),
+    };
+    send(ctx, &[ix], &[]).await.unwrap();
+}
+
+async fn hook_revoke_kyc(ctx: &mut ProgramTestContext, user: &Pubkey) {
+    let authority = ctx.payer.pubkey();
+    let ix = Instruction {
+        program_id: hook::ID,
+        accounts: hook::accounts::RevokeKyc {
+            kyc: kyc_address(user),
+            user: *user,
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+        }
+        .to_account_metas(None),
+        data: hook::instruction::RevokeKyc {}.data(),
+    };
+    send(ctx, &[ix], &[]).await.unwrap();
+}
+
+async fn hook_add_exempt_account(ctx: &mut ProgramTestContext, address: &Pubkey) {
+    let authority = ctx.payer.pubkey();
+    let ix = Instruction {
+        program_id: hook::ID,
+        accounts: hook::accounts::AddExemptAccount {
+            exempt_account: exempt_address(address),
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: hook::instruction::AddExemptAccount { address: *address }.data(),
+    };
+    send(ctx, &[ix], &[]).await.unwrap();
+}
+
+async fn hook_initialize_extra_account_metas(ctx: &mut ProgramTestContext, mint: &Pubkey) {
+    let authority = ctx.payer.pubkey();
+    let ix = Instruction {
+        program_id: hook::ID,
+        accounts: hook::accounts::InitializeExtraAccountMetaList {
+            extra_account_meta_list: extra_account_metas_address(mint),
+            mint: *mint,
+            settings: settings_address(),
+            role_assignment: None,
+            authority,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: hook::instruction::InitializeExtraAccountMetaList {}.data(),
+    };
+    send(ctx, &[ix], &[]).await.unwrap();
+}
+
+fn hook_ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: hook::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+// Gives the hook something to load for every extra account it reads on a swap
+// by `user`: mint config with tier limits, allowlist-only mode and a balance
+// cap, the user's usage, lockup, country and allowlist entry, a jurisdiction
+// policy, and KYC, exemption and allowlist records for the pool itself.
+// Denylist entries and exemptions for the user are left out, since either one
+// would end the checks early.
+async fn hook_configure_everything(env: &mut Env, user: &User) {
+    let authority = env.ctx.payer.pubkey();
+    let owner = user.keypair.pubkey();
+    let mint = env.mint;
+    let ixs = [
+        hook_ix(
+            hook::accounts::InitializeMintConfig {
+                mint_config: mint_config_address(&mint),
+                mint,
+                settings: settings_address(),
+                role_assignment: None,
+                authority,
+                system_program: system_program::ID,
+            },
+            hook::instruction::InitializeMintConfig {
+                authority,
+                policy: hook::MintPolicy {
+                    require_sender_kyc: true,
+                    require_recipient_kyc: true,
+                    limits_enabled: true,
+                    max_balance: Some(1_000_000 * TOKENS),
+                    allowlist_only: true,
+                },
+            },
+        ),
+        hook_ix(
+            hook::accounts::SetTransferLimits {
+                mint_config: mint_config_address(&mint),
+                settings: settings_address(),
+                role_assignment: None,
+                authority,
+            },
+            hook::instruction::SetTransferLimits {
+                level: hook::KycLevel::Basic,
+                limits: hook::TransferLimits {
+                    daily_limit: 100 * TOKENS,
+                    transaction_limit: 50 * TOKENS,
+                    weekly_limit: Some(200 * TOKENS),
+                    monthly_limit: Some(300 * TOKENS),
+                    max_daily_transfers: Some(10),
+                    cooldown_seconds: Some(60),
+                },
+            },
+        ),
+        hook_ix(
+            hook::accounts::CreateLockup {
+                lockup: lockup_address(&user.token_account),
+                token_account: user.token_account,
+                mint,
+                mint_config: None,
+                settings: settings_address(),
+                role_assignment: None,
+                authority,
+                system_program: system_program::ID,
+            },
+            hook::instruction::CreateLockup {
+                schedule: hook::LockupSchedule {
+                    amount: 10 * TOKENS,
+                    start_at: 0,
+                    cliff_at: i64::MAX / 2,
+                    end_at: i64::MAX,
+                },
+            },
+        ),
+        hook_ix(
+            hook::accounts::InitializeJurisdictionPolicy {
+                jurisdiction_policy: jurisdiction_address(&mint),
+                mint,
+                mint_config: None,
+                settings: settings_address(),
+                role_assignment: None,
+                authority,
+                system_program: system_program::ID,
+            },
+            hook::instruction::InitializeJurisdictionPolicy {
+                rules: hook::JurisdictionRules {
+                    mode: hook::JurisdictionMode::Blocklist,
+                    countries: vec![*b"US"],
+                    require_accredited: false,
+                },
+            },
+        ),
+        hook_ix(
+            hook::accounts::SetKycJurisdiction {
+                kyc: kyc_address(&owner),
+                user: owner,
+                settings: settings_address(),
+                role_assignment: None,
+                authority,
+            },
+            hook::instruction::SetKycJurisdiction {
+                country: *b"DE",
+                accredited: false,
+            },
+        ),
+        hook_ix(
+            hook::accounts::InitUserUsage {
+                user_usage: usage_address(&owner, &mint),
+                mint,
+                user: owner,
+                system_program: system_program::ID,
+            },
+            hook::instruction::InitUserUsage {},
+        ),
+    ];
+    send(&mut env.ctx, &ixs, &[&user.keypair]).await.unwrap();
+
+    for wallet in [owner, env.pool] {
+        let ix = hook_ix(
+            hook::accounts::AddToAllowlist {
+                allowlist_entry: allowlist_address(&mint, &wallet),
+                mint,
+                mint_config: None,
+                settings: settings_address(),
+                role_assignment: None,
+                authority,
+                system_program: system_program::ID,
+            },
+            hook::instruction::AddToAllowlist { wallet },
+        );
+        send(&mut env.ctx, &[ix], &[]).await.unwrap();
+    }
+    hook_create_kyc(&mut env.ctx, &env.pool).await;
+    hook_add_exempt_account(&mut env.ctx, &env.pool).await;
+}
+
+fn initialize_pool_ix(payer: Pubkey, mint: Pubkey, lp_mint: Pubkey) -> Instruction {
+    let (pool, token_vault, sol_vault) = pool_addresses(&mint);
+    Instruction {
+        program_id: amm::ID,
+        accounts: amm::accounts::InitializePool {
+            pool,
+            token_mint: mint,
+            token_vault,
+            sol_vault,
+            lp_mint,
+            payer,
+            token_program: spl_token_2022::id(),
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        }
+        .to_account_metas(None),
+        data: amm::instruction::InitializePool {}.data(),
+    }
+}
+
+// Hook program initialized, a hooked mint with its extra account metas, and an
+// empty pool whose token vault is exempt from KYC so it can pay out.
+async fn setup() -> Env {
+    setup_with(program_test()).await
+}
+
+async fn setup_with(program_test: ProgramTest) -> Env {
+    let mut ctx = program_test.start_with_context().await;
+
+    hook_initialize(&mut ctx).await;
+    let mint = create_hooked_mint(&mut ctx, hook::ID).await;
+    hook_initialize_extra_account_metas(&mut ctx, &mint).await;
+
+    let lp_mint = Keypair::new();
+    let (pool, token_vault, sol_vault) = pool_addresses(&mint);
+    let ix = initialize_pool_ix(ctx.payer.pubkey(), mint, lp_mint.pubkey());
+    send(&mut ctx, &[ix], &[&lp_mint]).await.unwrap();
+    hook_add_exempt_account(&mut ctx, &token_vault).await;
+
+    Env {
+        ctx,
+        mint,
+        pool,
+        token_vault,
+        sol_vault,
+        lp_mint: lp_mint.pubkey(),
+    }
+}
+
+impl Env {
+    async fn create_user(&mut self, lamports: u64, tokens: u64, kyc: bool) -> User {
+        let keypair = Keypair::new();
+        let owner = keypair.pubkey();
+        let payer = self.ctx.payer.pubkey();
+
+        send(
+            &mut self.ctx,
+            &[system_instruction::transfer(&payer, &owner, lamports)],
+            &[],
+        )
+        .await
+        .unwrap();
+
+        let token_account = create_token_account(
+            &mut self.ctx,
+            &self.mint,
+            &owner,
+            &[ExtensionType::TransferHookAccount],
+        )
+        .await;
+        let lp_token_account = create_token_account(&mut self.ctx, &self.lp_mint, &owner, &[]).await;
+
+        if tokens > 0 {
+            let ix = spl_token_2022::instruction::mint_to(
+                &spl_token_2022::id(),
+                &self.mint,
+                &token_account,
+                &payer,
+                &[],
+                tokens,
+            )
+            .unwrap();
+            send(&mut self.ctx, &[ix], &[]).await.unwrap();
+        }
+
+        if kyc {
+            hook_create_kyc(&mut self.ctx, &owner).await;
+        }
+
+        User {
+            keypair,
+            token_account,
+            lp_token_account,
+        }
+    }
+
+    async fn pool(&mut self) -> Pool {
+        let account = self.ctx.banks_client.get_account(self.pool).await.unwrap().unwrap();
+        Pool::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    async fn sol_reserve(&mut self) -> u64 {
+        self.ctx.banks_client.get_balance(self.sol_vault).await.unwrap()
+    }
+
+    async fn hook_accounts(&mut self, source: Pubkey, destination: Pubkey, owner: Pubkey) -> Vec<AccountMeta> {
+        support::hook_accounts(&mut self.ctx.banks_client, &source, &self.mint, &destination, &owner, 0).await
+    }
+
+    async fn add_liquidity_ix(&mut self, user: &User, token_amount: u64, sol_amount: u64, min_lp_tokens: u64) -> Instruction {
+        let mut accounts = amm::accounts::AddLiquidity {
+            pool: self.pool,
+            token_mint: self.mint,
+            token_vault: self.token_vault,
+            sol_vault: self.sol_vault,
+            lp_mint: self.lp_mint,
+            user_token_account: user.token_account,
+            user_lp_token_account: user.lp_token_account,
+            user: user.keypair.pubkey(),
+            token_program: spl_token_2022::id(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None);
+        let hook_accounts = self.hook_accounts(user.token_account, self.token_vault, user.keypair.pubkey()).await;
+        accounts.extend(hook_accounts);
+
+        Instruction {
+            program_id: amm::ID,
+            accounts,
+            data: amm::instruction::AddLiquidity {
+                token_amount,
+                sol_amount,
+                min_lp_tokens,
+            }
+            .data(),
+        }
+    }
+
+    async fn remove_liquidity_ix(&mut self, user: &User, lp_amount: u64) -> Instruction {
+        let mut accounts = amm::accounts::RemoveLiquidity {
+            pool: self.pool,
+            token_mint: self.mint,
+            token_vault: self.token_vault,
+            sol_vault: self.sol_vault,
+            lp_mint: self.lp_mint,
+            user_token_account: user.token_account,
+            user_lp_token_account: user.lp_token_account,
+            user: user.keypair.pubkey(),
+            token_program: spl_token_2022::id(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None);
+        let hook_accounts = self.hook_accounts(self.token_vault, user.token_account, self.pool).await;
+        accounts.extend(hook_accounts);
+
+        Instruction {
+            program_id: amm::ID,
+            accounts,
+            data: amm::instruction::RemoveLiquidity {
+                lp_amount,
+                min_token_amount: 0,
+                min_sol_amount: 0,
+            }
+            .data(),
+        }
+    }
+
+    async fn swap_token_for_sol_ix(&mut self, user: &User, token_amount: u64, min_sol_out: u64) -> Instruction {
+        let mut accounts = amm::accounts::SwapToken {
+            pool: self.pool,
+            token_mint: self.mint,
+            token_vault: self.token_vault,
+            sol_vault: self.sol_vault,
+            user_token_account: user.token_account,
+            user: user.keypair.pubkey(),
+            token_program: spl_token_2022::id(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None);
+        let hook_accounts = self.hook_accounts(user.token_account, self.token_vault, user.keypair.pubkey()).await;
+        accounts.extend(hook_accounts);
+
+        Instruction {
+            program_id: amm::ID,
+            accounts,
+            data: amm::instruction::SwapTokenForSol {
+                token_amount,
+                min_sol_out,
+            }
+            .data(),
+        }
+    }
+
+    async fn swap_sol_for_token_ix(&mut self, user: &User, lamport_amount: u64, min_token_out: u64) -> Instruction {
+        let mut accounts = amm::accounts::SwapSol {
+            pool: self.pool,
+            token_mint: self.mint,
+            token_vault: self.token_vault,
+            sol_vault: self.sol_vault,
+            user_token_account: user.token_account,
+            user: user.keypair.pubkey(),
+            token_program: spl_token_2022::id(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None);
+        let hook_accounts = self.hook_accounts(self.token_vault, user.token_account, self.pool).await;
+        accounts.extend(hook_accounts);
+
+        Instruction {
+            program_id: amm::ID,
+            accounts,
+            data: amm::instruction::SwapSolForToken {
+                lamport_amount,
+                min_token_out,
+            }
+            .data(),
+        }
+    }
+
+    fn quote_accounts(&self) -> Vec<AccountMeta> {
+        amm::accounts::Quote {
+            pool: self.pool,
+            token_mint: self.mint,
+            token_vault: self.token_vault,
+            sol_vault: self.sol_vault,
+            lp_mint: self.lp_mint,
+        }
+        .to_account_metas(None)
+    }
+
+    async fn simulate<T: AnchorDeserialize>(&mut self, ix: Instruction) -> T {
+        let blockhash = self.ctx.banks_client.get_latest_blockhash().await.unwrap();
+        let transaction = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&self.ctx.payer.pubkey()),
+            &[&self.ctx.payer],
+            blockhash,
+        );
+        let simulation = self.ctx.banks_client.simulate_transaction(transaction).await.unwrap();
+        simulation.result.unwrap().unwrap();
+        let return_data = simulation.simulation_details.unwrap().return_data.unwrap();
+        assert_eq!(return_data.program_id, amm::ID);
+        T::deserialize(&mut return_data.data.as_slice()).unwrap()
+    }
+
+    async fn quote_swap(&mut self, amount_in: u64, direction: SwapDirection) -> SwapQuote {
+        let ix = Instruction {
+            program_id: amm::ID,
+            accounts: self.quote_accounts(),
+            data: amm::instruction::QuoteSwap {
+                amount_in,
+                direction,
+            }
+            .data(),
+        };
+        self.simulate(ix).await
+    }
+
+    async fn quote_add_liquidity(&mut self, token_amount: u64) -> LiquidityQuote {
+        let ix = Instruction {
+            program_id: amm::ID,
+            accounts: self.quote_accounts(),
+            data: amm::instruction::QuoteAddLiquidity { token_amount }.data(),
+        };
+        self.simulate(ix).await
+    }
+
+    async fn quote_remove_liquidity(&mut self, lp_amount: u64) -> LiquidityQuote {
+        let ix = Instruction {
+            program_id: amm::ID,
+            accounts: self.quote_accounts(),
+            data: amm::instruction::QuoteRemoveLiquidity { lp_amount }.data(),
+        };
+        self.simulate(ix).await
+    }
+
+    // Rewrites the mint's extra account meta list so the hook is handed the pool,
+    // its SOL vault, the LP mint and the AMM program on every transfer.
+    async fn point_hook_at_pool(&mut self) {
+        let metas = [self.pool, self.sol_vault, self.lp_mint, amm::ID]
+            .map(|address| ExtraAccountMeta::new_with_pubkey(&address, false, false).unwrap());
+        let mut data = vec![0; ExtraAccountMetaList::size_of(metas.len()).unwrap()];
+        ExtraAccountMetaList::init::<ExecuteInstruction>(&mut data, &metas).unwrap();
+
+        let validation = extra_account_metas_address(&self.mint);
+        let mut account = self.ctx.banks_client.get_account(validation).await.unwrap().unwrap();
+        account.data = data;
+        self.ctx.set_account(&validation, &account.into());
+    }
+
+    // Pool seeded with `lamports` and `tokens` by a dedicated provider, who keeps
+    // one SOL on top.
+    async fn seed_pool(&mut self, lamports: u64, tokens: u64) -> User {
+        let provider = self.create_user(lamports + LAMPORTS_PER_SOL, tokens, true).await;
+        let ix = self.add_liquidity_ix(&provider, tokens, lamports, 0).await;
+        send(&mut self.ctx, &[ix], &[&provider.keypair]).await.unwrap();
+        provider
+    }
+}
+
+#[tokio::test]
+async fn initialize_pool_records_vaults() {
+    let mut env = setup().await;
+    let pool = env.pool().await;
+
+    assert_eq!(pool.token_mint, env.mint);
+    assert_eq!(pool.token_vault, env.token_vault);
+    assert_eq!(pool.sol_vault, env.sol_vault);
+    assert_eq!(pool.lp_mint, env.lp_mint);
+    assert!(pool.is_active);
+}
+
+#[tokio::test]
+async fn initialize_pool_rejects_unlisted_hook() {
+    let mut ctx = program_test().start_with_context().await;
+    let mint = create_hooked_mint(&mut ctx, Pubkey::new_unique()).await;
+    let lp_mint = Keypair::new();
+
+    let ix = initialize_pool_ix(ctx.payer.pubkey(), mint, lp_mint.pubkey());
+    let result = send(&mut ctx, &[ix], &[&lp_mint]).await;
+
+    assert_custom_error(result, amm::ErrorCode::UnsupportedTransferHook.into());
+}
+
+#[tokio::test]
+async fn add_and_remove_liquidity() {
+    let mut env = setup().await;
+
+    // An empty pool quotes the initial LP amount and leaves the SOL to the depositor
+    let quote = env.quote_add_liquidity(1_000 * TOKENS).await;
+    assert_eq!(quote.sol_amount, 0);
+    assert_eq!(quote.lp_amount, 1_000 * TOKENS * amm::math::INITIAL_LP_MULTIPLIER);
+
+    let provider = env.seed_pool(10 * LAMPORTS_PER_SOL, 1_000 * TOKENS).await;
+
+    assert_eq!(token_balance(&mut env.ctx, &env.token_vault).await, 1_000 * TOKENS);
+    assert_eq!(env.sol_reserve().await, 10 * LAMPORTS_PER_SOL);
+    assert_eq!(env.ctx.banks_client.get_balance(provider.keypair.pubkey()).await.unwrap(), LAMPORTS_PER_SOL);
+    let lp_balance = token_balance(&mut env.ctx, &provider.lp_token_account).await;
+    assert_eq!(lp_balance, quote.lp_amount);
+
+    // A second provider deposits at the pool ratio, within their SOL cap
+    let user = env.create_user(5 * LAMPORTS_PER_SOL, 100 * TOKENS, true).await;
+    let quote = env.quote_add_liquidity(100 * TOKENS).await;
+    assert_eq!(quote.sol_amount, LAMPORTS_PER_SOL);
+    let ix = env.add_liquidity_ix(&user, 100 * TOKENS, quote.sol_amount - 1, 0).await;
+    let result = send(&mut env.ctx, &[ix], &[&user.keypair]).await;
+    assert_custom_error(result, amm::ErrorCode::SlippageExceeded.into());
+    let ix = env.add_liquidity_ix(&user, 100 * TOKENS, quote.sol_amount, 0).await;
+    send(&mut env.ctx, &[ix], &[&user.keypair]).await.unwrap();
+
+    assert_eq!(env.sol_reserve().await, 11 * LAMPORTS_PER_SOL);
+    let user_lp = token_balance(&mut env.ctx, &user.lp_token_account).await;
+    assert_eq!(user_lp, lp_balance / 10);
+
+    // ...and withdraws exactly what the quote promised
+    let quote = env.quote_remove_liquidity(user_lp).await;
+    let ix = env.remove_liquidity_ix(&user, user_lp).await;
+    send(&mut env.ctx, &[ix], &[&user.keypair]).await.unwrap();
+
+    assert_eq!(token_balance(&mut env.ctx, &user.token_account).await, quote.token_amount);
+    assert_eq!(quote.token_amount, 100 * TOKENS);
+    assert_eq!(env.sol_reserve().await, 11 * LAMPORTS_PER_SOL - quote.sol_amount);
+    assert_eq!(token_balance(&mut env.ctx, &user.lp_token_account).await, 0);
+}
+
+#[tokio::test]
+async fn swaps_match_quotes() {
+    let mut env = setup().await;
+    env.seed_pool(10 * LAMPORTS_PER_SOL, 1_000 * TOKENS).await;
+    let user = env.create_user(5 * LAMPORTS_PER_SOL, 50 * TOKENS, true).await;
+
+    // Token -> SOL
+    let quote = env.quote_swap(50 * TOKENS, SwapDirection::TokenToSol).await;
+    let sol_before = env.sol_reserve().await;
+    let ix = env.swap_token_for_sol_ix(&user, 50 * TOKENS, quote.amount_out).await;
+    send(&mut env.ctx, &[ix], &[&user.keypair]).await.unwrap();
+
+    assert_eq!(token_balance(&mut env.ctx, &user.token_account).await, 0);
+    assert_eq!(env.sol_reserve().await, sol_before - quote.amount_out);
+
+    // SOL -> token
+    let quote = env.quote_swap(LAMPORTS_PER_SOL, SwapDirection::SolToToken).await;
+    let ix = env.swap_sol_for_token_ix(&user, LAMPORTS_PER_SOL, quote.amount_out).await;
+    send(&mut env.ctx, &[ix], &[&user.keypair]).await.unwrap();
+
+    assert_eq!(token_balance(&mut env.ctx, &user.token_account).await, quote.amount_out);
+}
+
+#[tokio::test]
+async fn swap_rejects_slippage() {
+    let mut env = setup().await;
+    env.seed_pool(10 * LAMPORTS_PER_SOL, 1_000 * TOKENS).await;
+    let user = env.create_user(LAMPORTS_PER_SOL, 50 * TOKENS, true).await;
+
+    let quote = env.quote_swap(50 * TOKENS, SwapDirection::TokenToSol).await;
+    let ix = env.swap_token_for_sol_ix(&user, 50 * TOKENS, quote.amount_out + 1).await;
+    let result = send(&mut env.ctx, &[ix], &[&user.keypair]).await;
+
+    assert_custom_error(result, amm::ErrorCode::SlippageExceeded.into());
+}
+
+#[tokio::test]
+async fn swap_requires_kyc() {
+    let mut env = setup().await;
+    env.seed_pool(10 * LAMPORTS_PER_SOL, 1_000 * TOKENS).await;
+
+    // No KYC record at all
+    let user = env.create_user(LAMPORTS_PER_SOL, 50 * TOKENS, false).await;
+    let ix = env.swap_token_for_sol_ix(&user, 10 * TOKENS, 0).await;
+    assert!(send(&mut env.ctx, &[ix], &[&user.keypair]).await.is_err());
+
+    // Approved, then revoked
+    let user = env.create_user(LAMPORTS_PER_SOL, 50 * TOKENS, true).await;
+    let ix = env.swap_token_for_sol_ix(&user, 10 * TOKENS, 0).await;
+    send(&mut env.ctx, &[ix], &[&user.keypair]).await.unwrap();
+
+    hook_revoke_kyc(&mut env.ctx, &user.keypair.pubkey()).await;
+    let ix = env.swap_token_for_sol_ix(&user, 5 * TOKENS, 0).await;
+    let result = send(&mut env.ctx, &[ix], &[&user.keypair]).await;
+
+    assert_custom_error(result, hook::ErrorCode::KycFailed.into());
+    assert_eq!(token_balance(&mut env.ctx, &user.token_account).await, 40 * TOKENS);
+}
+
+#[tokio::test]
+async fn swap_releases_pool_lock() {
+    let mut env = setup().await;
+    env.seed_pool(10 * LAMPORTS_PER_SOL, 1_000 * TOKENS).await;
+    let user = env.create_user(LAMPORTS_PER_SOL, 50 * TOKENS, true).await;
+
+    let ix = env.swap_token_for_sol_ix(&user, 10 * TOKENS, 0).await;
+    send(&mut env.ctx, &[ix], &[&user.keypair]).await.unwrap();
+
+    assert!(!env.pool().await.locked);
+}
+
+#[tokio::test]
+async fn swap_rejects_vault_aliasing() {
+    let mut env = setup().await;
+    env.seed_pool(10 * LAMPORTS_PER_SOL, 1_000 * TOKENS).await;
+    let mut user = env.create_user(LAMPORTS_PER_SOL, 50 * TOKENS, true).await;
+
+    user.token_account = env.token_vault;
+    let ix = env.swap_sol_for_token_ix(&user, LAMPORTS_PER_SOL, 0).await;
+    let result = send(&mut env.ctx, &[ix], &[&user.keypair]).await;
+
+    assert_custom_error(result, amm::ErrorCode::TokenVaultAliased.into());
+}
+
+#[tokio::test]
+async fn swap_with_every_hook_account_present() {
+    let mut env = setup().await;
+    env.seed_pool(10 * LAMPORTS_PER_SOL, 1_000 * TOKENS).await;
+    let user = env.create_user(5 * LAMPORTS_PER_SOL, 50 * TOKENS, true).await;
+    hook_configure_everything(&mut env, &user).await;
+
+    let ix = env.swap_token_for_sol_ix(&user, 20 * TOKENS, 0).await;
+    send(&mut env.ctx, &[ix], &[&user.keypair]).await.unwrap();
+    let ix = env.swap_sol_for_token_ix(&user, LAMPORTS_PER_SOL, 0).await;
+    send(&mut env.ctx, &[ix], &[&user.keypair]).await.unwrap();
+
+    let usage = env
+        .ctx
+        .banks_client
+        .get_account(usage_address(&user.keypair.pubkey(), &env.mint))
+        .await
+        .unwrap()
+        .unwrap();
+    let usage = hook::UserUsage::try_deserialize(&mut usage.data.as_slice()).unwrap();
+    assert_eq!(usage.last_24h(), Some(20 * TOKENS));
+    assert!(!env.pool().await.locked);
+}
+
+#[tokio::test]
+async fn hook_cannot_reenter_the_amm() {
+    let mut program_test = ProgramTest::default();
+    program_test.add_program("amm", amm::ID, processor!(amm_processor));
+    program_test.add_program("hook", hook::ID, processor!(reentrant_hook_processor));
+    let mut env = setup_with(program_test).await;
+    env.seed_pool(10 * LAMPORTS_PER_SOL, 1_000 * TOKENS).await;
+    let user = env.create_user(LAMPORTS_PER_SOL, 50 * TOKENS, true).await;
+    env.point_hook_at_pool().await;
+
+    let ix = env.swap_token_for_sol_ix(&user, 10 * TOKENS, 0).await;
+    let result = send(&mut env.ctx, &[ix], &[&user.keypair]).await;
+
+    assert_custom_error(result, amm::ErrorCode::PoolLocked.into());
+    assert_eq!(token_balance(&mut env.ctx, &user.token_account).await, 50 * TOKENS);
+    assert!(!env.pool().await.locked);
+}
@@ -0,0 +1,250 @@
+use amm::math::{
+    amounts_for_lp, get_amount_in, get_amount_out, lp_for_deposit, swap_fee, MathError,
+};
+use proptest::prelude::*;
+
+const FEE_NUMERATOR: u64 = 3;
+const FEE_DENOMINATOR: u64 = 1000;
+
+// Reserves and amounts kept below 10^15 so the invariant checks themselves fit in u128.
+const MAX_RESERVE: u64 = 1_000_000_000_000_000;
+
+fn reserve() -> impl Strategy<Value = u64> {
+    1..MAX_RESERVE
+}
+
+fn fee() -> impl Strategy<Value = (u64, u64)> {
+    (1u64..=10_000).prop_flat_map(|denominator| (0..denominator, Just(denominator)))
+}
+
+proptest! {
+    #[test]
+    fn swap_never_decreases_k(
+        reserve_in in reserve(),
+        reserve_out in reserve(),
+        amount_in in 0..MAX_RESERVE,
+        (fee_numerator, fee_denominator) in fee(),
+    ) {
+        let amount_out =
+            get_amount_out(amount_in, reserve_in, reserve_out, fee_numerator, fee_denominator)
+                .unwrap();
+
+        prop_assert!(amount_out < reserve_out);
+
+        let k_before = reserve_in as u128 * reserve_out as u128;
+        let k_after = (reserve_in + amount_in) as u128 * (reserve_out - amount_out) as u128;
+        prop_assert!(k_after >= k_before);
+    }
+
+    #[test]
+    fn repeated_swaps_never_decrease_k(
+        mut token_reserve in reserve(),
+        mut sol_reserve in reserve(),
+        swaps in prop::collection::vec((any::<bool>(), 1..MAX_RESERVE / 16), 1..16),
+    ) {
+        let k_initial = token_reserve as u128 * sol_reserve as u128;
+
+        for (token_to_sol, amount_in) in swaps {
+            let (reserve_in, reserve_out) = if token_to_sol {
+                (&mut token_reserve, &mut sol_reserve)
+            } else {
+                (&mut sol_reserve, &mut token_reserve)
+            };
+
+            let amount_out =
+                get_amount_out(amount_in, *reserve_in, *reserve_out, FEE_NUMERATOR, FEE_DENOMINATOR)
+                    .unwrap();
+            *reserve_in += amount_in;
+            *reserve_out -= amount_out;
+        }
+
+        prop_assert!(token_reserve as u128 * sol_reserve as u128 >= k_initial);
+    }
+
+    #[test]
+    fn amount_in_rounds_in_favor_of_pool(
+        reserve_in in reserve(),
+        reserve_out in 2..MAX_RESERVE,
+        out_fraction in 1u64..1_000,
+    ) {
+        let amount_out = (reserve_out as u128 * out_fraction as u128 / 1_000) as u64;
+        prop_assume!(amount_out > 0 && amount_out < reserve_out);
+
+        let amount_in =
+            get_amount_in(amount_out, reserve_in, reserve_out, FEE_NUMERATOR, FEE_DENOMINATOR);
+
+        // Very lopsided pools can legitimately need more than u64::MAX input.
+        if let Ok(amount_in) = amount_in {
+            let received =
+                get_amount_out(amount_in, reserve_in, reserve_out, FEE_NUMERATOR, FEE_DENOMINATOR)
+                    .unwrap();
+            prop_assert!(received >= amount_out);
+
+            if amount_in > 1 {
+                let short = get_amount_out(
+                    amount_in - 1,
+                    reserve_in,
+                    reserve_out,
+                    FEE_NUMERATOR,
+                    FEE_DENOMINATOR,
+                )
+                .unwrap();
+                prop_assert!(short < amount_out);
+            }
+        } else {
+            prop_assert_eq!(amount_in, Err(MathError::MathOverflow));
+        }
+    }
+
+    #[test]
+    fn swap_fee_rounds_up(amount_in in any::<u64>(), (fee_numerator, fee_denominator) in fee()) {
+        let fee = swap_fee(amount_in, fee_numerator, fee_denominator).unwrap();
+        let exact_times_denominator = amount_in as u128 * fee_numerator as u128;
+
+        prop_assert!(fee as u128 * fee_denominator as u128 >= exact_times_denominator);
+        if fee > 0 {
+            prop_assert!((fee as u128 - 1) * (fee_denominator as u128) < exact_times_denominator);
+        } else {
+            prop_assert_eq!(exact_times_denominator, 0);
+        }
+    }
+
+    #[test]
+    fn add_then_remove_never_returns_more_than_deposited(
+        token_reserve in reserve(),
+        sol_reserve in reserve(),
+        lp_supply in reserve(),
+        token_amount in 0..MAX_RESERVE,
+    ) {
+        let (sol_amount, lp_minted) =
+            match lp_for_deposit(token_amount, token_reserve, sol_reserve, lp_supply) {
+                Ok(deposit) => deposit,
+                Err(err) => {
+                    prop_assert_eq!(err, MathError::MathOverflow);
+                    return Ok(());
+                }
+            };
+
+        let (token_out, sol_out) = amounts_for_lp(
+            lp_minted,
+            token_reserve + token_amount,
+            sol_reserve + sol_amount,
+            lp_supply + lp_minted,
+        )
+        .unwrap();
+
+        prop_assert!(token_out <= token_amount);
+        prop_assert!(sol_out <= sol_amount);
+    }
+
+    #[test]
+    fn deposit_rounds_in_favor_of_pool(
+        token_reserve in reserve(),
+        sol_reserve in reserve(),
+        lp_supply in reserve(),
+        token_amount in 0..MAX_RESERVE,
+    ) {
+        if let Ok((sol_amount, lp_minted)) =
+            lp_for_deposit(token_amount, token_reserve, sol_reserve, lp_supply)
+        {
+            // The depositor pays at least the pool price for SOL...
+            prop_assert!(
+                sol_amount as u128 * token_reserve as u128
+                    >= token_amount as u128 * sol_reserve as u128
+            );
+            // ...and is never credited more than their share of either reserve.
+            prop_assert!(
+                lp_minted as u128 * token_reserve as u128
+                    <= token_amount as u128 * lp_supply as u128
+            );
+            prop_assert!(
+                lp_minted as u128 * sol_reserve as u128
+                    <= sol_amount as u128 * lp_supply as u128
+            );
+        }
+    }
+
+    #[test]
+    fn withdrawal_rounds_in_favor_of_pool(
+        token_reserve in any::<u64>(),
+        sol_reserve in any::<u64>(),
+        lp_supply in 1..u64::MAX,
+        lp_fraction in 0u64..=1_000,
+    ) {
+        let lp_amount = (lp_supply as u128 * lp_fraction as u128 / 1_000) as u64;
+        let (token_out, sol_out) =
+            amounts_for_lp(lp_amount, token_reserve, sol_reserve, lp_supply).unwrap();
+
+        prop_assert!(
+            token_out as u128 * lp_supply as u128 <= token_reserve as u128 * lp_amount as u128
+        );
+        prop_assert!(sol_out as u128 * lp_supply as u128 <= sol_reserve as u128 * lp_amount as u128);
+    }
+
+    #[test]
+    fn pricing_never_panics_or_masks_overflow(
+        a in any::<u64>(),
+        b in any::<u64>(),
+        c in any::<u64>(),
+        d in any::<u64>(),
+        fee_numerator in any::<u64>(),
+        fee_denominator in any::<u64>(),
+    ) {
+        // A zero result must be the true floor of the exact value, never a
+        // swallowed overflow.
+        if let Ok(0) = get_amount_out(a, b, c, fee_numerator, fee_denominator) {
+            let amount_in_with_fee = a as u128 * (fee_denominator - fee_numerator) as u128;
+            let numerator = amount_in_with_fee.checked_mul(c as u128);
+            prop_assert!(numerator.is_some());
+            if let Some(denominator) =
+                (b as u128 * fee_denominator as u128).checked_add(amount_in_with_fee)
+            {
+                prop_assert!(numerator.unwrap() < denominator);
+            }
+        }
+
+        let _ = get_amount_in(a, b, c, fee_numerator, fee_denominator);
+        let _ = swap_fee(a, fee_numerator, fee_denominator);
+
+        if let Ok((sol_amount, lp_tokens)) = lp_for_deposit(a, b, c, d) {
+            prop_assert!(a == 0 || sol_amount > 0 || c == 0);
+            if lp_tokens == 0 && d > 0 {
+                prop_assert!(
+                    (a as u128) * (d as u128) < b as u128
+                        || (sol_amount as u128) * (d as u128) < c as u128
+                );
+            }
+        }
+
+        if let Ok((token_out, sol_out)) = amounts_for_lp(a, b, c, d) {
+            if token_out == 0 {
+                prop_assert!((b as u128) * (a as u128) < d as u128);
+            }
+            if sol_out == 0 {
+                prop_assert!((c as u128) * (a as u128) < d as u128);
+            }
+        }
+    }
+}
+
+#[test]
+fn empty_pool_is_rejected() {
+    assert_eq!(
+        get_amount_out(1, 0, 1, FEE_NUMERATOR, FEE_DENOMINATOR),
+        Err(MathError::InsufficientLiquidity)
+    );
+    assert_eq!(
+        get_amount_in(1, 1, 1, FEE_NUMERATOR, FEE_DENOMINATOR),
+        Err(MathError::InsufficientLiquidity)
+    );
+    assert_eq!(lp_for_deposit(1, 0, 1, 1), Err(MathError::InsufficientLiquidity));
+    assert_eq!(amounts_for_lp(1, 1, 1, 0), Err(MathError::InsufficientLiquidity));
+    assert_eq!(amounts_for_lp(2, 1, 1, 1), Err(MathError::InsufficientLiquidity));
+}
+
+#[test]
+fn invalid_fee_is_rejected() {
+    assert_eq!(get_amount_out(1, 1, 1, 1, 0), Err(MathError::InvalidFee));
+    assert_eq!(get_amount_out(1, 1, 1, 5, 5), Err(MathError::InvalidFee));
+    assert_eq!(swap_fee(1, 2, 1), Err(MathError::InvalidFee));
+}
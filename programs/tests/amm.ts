@@ -209,10 +209,11 @@ describe("AMM Program", () => {
 
     // Add liquidity
     const tokenAmount = new anchor.BN(100000000); // 0.1 tokens
+    const solAmount = new anchor.BN(LAMPORTS_PER_SOL / 10); // sets the initial price
     const minLpTokens = new anchor.BN(100000); // 0.0001 LP tokens
 
     const tx = await program.methods
-      .addLiquidity(tokenAmount, minLpTokens)
+      .addLiquidity(tokenAmount, solAmount, minLpTokens)
       .accounts({
         pool: pool,
         tokenMint: tokenMint.publicKey,